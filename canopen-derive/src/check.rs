@@ -0,0 +1,111 @@
+use darling::{Error, Result};
+
+use crate::object::{DataType, Object, Record};
+
+/// Catch common mistakes that would only show up when talking to the node.
+///
+/// Expects objects to be sorted by index and subindex.
+pub fn check_objects(objects: &[Object], records: &[Record]) -> Result<()> {
+    let mut errors = Error::accumulator();
+
+    for object in objects {
+        match object.index {
+            0x0000 => {
+                errors.push(Error::custom("index 0x0000 is not used").with_span(&object.ident))
+            }
+            0x0020..=0x0FFF => errors.push(
+                Error::custom(format!(
+                    "index 0x{:04X} is reserved for data type definitions",
+                    object.index
+                ))
+                .with_span(&object.ident),
+            ),
+            _ => {}
+        }
+
+        let (Some(typ), Some(expected)) = (
+            object.typ,
+            communication_type(object.index, object.subindex),
+        ) else {
+            continue;
+        };
+        if !expected.is_compatible(typ) {
+            errors.push(
+                Error::custom(format!(
+                    "object 0x{:04X}sub{} must be {expected:?}, not {typ:?}",
+                    object.index, object.subindex
+                ))
+                .with_span(&object.ident),
+            );
+        }
+    }
+
+    for record in records.iter().filter(|r| !r.is_array) {
+        let has_subindex_0 = objects
+            .binary_search_by_key(&(record.index, 0), |o| (o.index, o.subindex))
+            .is_ok();
+        if !has_subindex_0 {
+            errors.push(
+                Error::custom(format!(
+                    "record 0x{:04X} needs subindex 0 with the highest subindex",
+                    record.index
+                ))
+                .with_span(&record.ident),
+            );
+        }
+    }
+
+    errors.finish()
+}
+
+/// Data type of the objects of the communication profile area, taken from CiA 301
+fn communication_type(index: u16, subindex: u8) -> Option<DataType> {
+    use DataType::*;
+
+    let is_tpdo_communication = matches!(index, 0x1800..=0x19FF);
+    let is_pdo_communication = is_tpdo_communication || matches!(index, 0x1400..=0x15FF);
+    let is_pdo_mapping = matches!(index, 0x1600..=0x17FF | 0x1A00..=0x1BFF);
+
+    let typ = match (index, subindex) {
+        // device type, error register and manufacturer status register
+        (0x1000, 0) | (0x1002, 0) => UNSIGNED32,
+        (0x1001, 0) => UNSIGNED8,
+        // pre-defined error field
+        (0x1003, 0) => UNSIGNED8,
+        (0x1003, _) => UNSIGNED32,
+        // COB-ID SYNC, communication cycle period and synchronous window length
+        (0x1005..=0x1007, 0) => UNSIGNED32,
+        // manufacturer device name, hardware and software version
+        (0x1008..=0x100A, 0) => VISIBLE_STRING,
+        // guard time and life time factor
+        (0x100C, 0) => UNSIGNED16,
+        (0x100D, 0) => UNSIGNED8,
+        // store parameters, restore default parameters and consumer heartbeat time
+        (0x1010 | 0x1011 | 0x1016, 0) => UNSIGNED8,
+        (0x1010 | 0x1011 | 0x1016, _) => UNSIGNED32,
+        // COB-ID time stamp and COB-ID EMCY
+        (0x1012 | 0x1014, 0) => UNSIGNED32,
+        // inhibit time EMCY and producer heartbeat time
+        (0x1015 | 0x1017, 0) => UNSIGNED16,
+        // identity object
+        (0x1018, 0) => UNSIGNED8,
+        (0x1018, 1..=4) => UNSIGNED32,
+        // synchronous counter overflow value
+        (0x1019, 0) => UNSIGNED8,
+        // SDO server parameter
+        (0x1200..=0x127F, 0) => UNSIGNED8,
+        (0x1200..=0x127F, 1 | 2) => UNSIGNED32,
+        (0x1200..=0x127F, 3) => UNSIGNED8,
+        (_, 0) if is_pdo_communication || is_pdo_mapping => UNSIGNED8,
+        // COB-ID, transmission type, inhibit time, event timer and SYNC start value
+        (_, 1) if is_pdo_communication => UNSIGNED32,
+        (_, 2) if is_pdo_communication => UNSIGNED8,
+        (_, 3 | 5) if is_pdo_communication => UNSIGNED16,
+        (_, 6) if is_tpdo_communication => UNSIGNED8,
+        (_, 1..=0x40) if is_pdo_mapping => UNSIGNED32,
+        // NMT startup
+        (0x1F80, 0) => UNSIGNED32,
+        _ => return None,
+    };
+    Some(typ)
+}
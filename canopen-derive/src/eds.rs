@@ -1,16 +1,87 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io;
 use std::io::Write;
 use std::path::Path;
 
-use crate::object::Record;
+use crate::object::{DataType, Record};
 use crate::Object;
 
+/// An object as written to the EDS file
+///
+/// Unlike [`Object`] it holds no spans, so it can be kept from the derive of a
+/// `#[canopen(record)]` struct to the derive of a struct nesting it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EdsObject {
+    subindex: u8,
+    name: String,
+    typ: Option<DataType>,
+    low_limit: Option<String>,
+    high_limit: Option<String>,
+    access_type: &'static str,
+    default: Option<String>,
+}
+
+impl EdsObject {
+    pub fn new(object: &Object, parent: Option<&Record>) -> Self {
+        EdsObject {
+            subindex: object.subindex,
+            name: object.parameter_name(parent),
+            typ: object.typ,
+            low_limit: object.min.as_ref().map(ToString::to_string),
+            high_limit: object.max.as_ref().map(ToString::to_string),
+            access_type: if object.read_only {
+                "ro"
+            } else if object.write_only {
+                "wo"
+            } else {
+                "rw"
+            }, // TODO const
+            default: object.default.as_ref().map(ToString::to_string),
+        }
+    }
+}
+
+thread_local! {
+    /// Objects of the `#[canopen(record)]` structs derived so far, by struct name
+    static RECORDS: RefCell<HashMap<String, Vec<EdsObject>>> = RefCell::default();
+}
+
+/// Remember the objects of a `#[canopen(record)]` struct for structs nesting it.
+pub fn register_record(name: String, objects: Vec<EdsObject>) {
+    RECORDS.with(|records| records.borrow_mut().insert(name, objects));
+}
+
+/// The objects of a `#[canopen(record)]` struct derived earlier in the same crate
+pub fn record_objects(name: &str) -> Option<Vec<EdsObject>> {
+    RECORDS.with(|records| records.borrow().get(name).cloned())
+}
+
+/// A record nested at `index`, with the objects of its fields
+pub struct NestedEds {
+    pub index: u16,
+    pub objects: Vec<EdsObject>,
+}
+
 // expects objects and records to be sorted by index and subindex
-pub fn write_eds(path: &Path, objects: &[Object], records: &[Record]) -> io::Result<()> {
+pub fn write_eds(
+    path: &Path,
+    objects: &[Object],
+    records: &[Record],
+    nested: &[NestedEds],
+) -> io::Result<()> {
     let mut file = File::create(path).expect("Failed to create file");
+    write_eds_into(&mut file, objects, records, nested)
+}
 
-    write_preamble(&mut file)?;
+fn write_eds_into(
+    out: &mut impl Write,
+    objects: &[Object],
+    records: &[Record],
+    nested: &[NestedEds],
+) -> io::Result<()> {
+    write_preamble(out)?;
 
     let mut record_iter = records.iter();
     let mut record_cursor = record_iter.next();
@@ -20,78 +91,145 @@ pub fn write_eds(path: &Path, objects: &[Object], records: &[Record]) -> io::Res
         // write record if it exists and matches the current object
         if let Some(r) = record_cursor {
             if r.index == object.index {
-                write_record(&mut file, r)?;
+                write_record(out, r)?;
                 current_record = record_cursor;
                 record_cursor = record_iter.next();
             }
         }
         // reset current_record if the current object does not match
-        if !current_record.is_some_and(|r| r.index == object.index) {
+        if current_record.is_none_or(|r| r.index != object.index) {
             current_record = None;
         }
-        write_object(&mut file, object, current_record)?;
+        let is_in_record = current_record.is_some();
+        write_object(
+            out,
+            object.index,
+            &EdsObject::new(object, current_record),
+            is_in_record,
+        )?;
+
+        // the fields of a nested record follow its subindex 0
+        if object.subindex == 0 {
+            if let Some(record) = nested.iter().find(|r| r.index == object.index) {
+                for field in &record.objects {
+                    write_object(out, object.index, field, true)?;
+                }
+            }
+        }
     }
 
     Ok(())
 }
 
-fn write_preamble(file: &mut File) -> io::Result<()> {
+fn write_preamble(out: &mut impl Write) -> io::Result<()> {
     writeln!(
-        file,
+        out,
         "; This file is automatically @generated by the canopen crate."
     )?;
-    writeln!(file, "; It is not intended for manual editing.")?;
-    writeln!(file)?;
-    writeln!(file, "[DeviceInfo]")?;
+    writeln!(out, "; It is not intended for manual editing.")?;
+    writeln!(out)?;
+    writeln!(out, "[DeviceInfo]")?;
     Ok(())
 }
 
-fn write_record(file: &mut File, object: &Record) -> io::Result<()> {
-    writeln!(file)?;
-    writeln!(file, "[{:X}]", object.index)?;
-    writeln!(file, "ParameterName={}", object.name)?;
+fn write_record(out: &mut impl Write, object: &Record) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "[{:X}]", object.index)?;
+    writeln!(out, "ParameterName={}", object.name)?;
     if object.is_array {
-        writeln!(file, "ObjectType=0x08")?;
+        writeln!(out, "ObjectType=0x08")?;
     } else {
-        writeln!(file, "ObjectType=0x09")?;
+        writeln!(out, "ObjectType=0x09")?;
     }
     Ok(())
 }
 
-fn write_object(file: &mut File, object: &Object, parent: Option<&Record>) -> io::Result<()> {
-    writeln!(file)?;
+fn write_object(
+    out: &mut impl Write,
+    index: u16,
+    object: &EdsObject,
+    is_in_record: bool,
+) -> io::Result<()> {
+    writeln!(out)?;
 
     // [XXXX] or [XXXXsubXX]
-    if object.subindex == 0 && parent.is_none() {
-        writeln!(file, "[{:X}]", object.index)?;
+    if object.subindex == 0 && !is_in_record {
+        writeln!(out, "[{index:X}]")?;
     } else {
-        writeln!(file, "[{:X}sub{:X}]", object.index, object.subindex)?;
+        writeln!(out, "[{index:X}sub{:X}]", object.subindex)?;
     }
     // ParameterName=...
-    let object_name = object.name.clone();
-    let record_name = parent.map(|r| r.name.clone());
-    let ident_name = object.ident.to_string();
-    writeln!(
-        file,
-        "ParameterName={}",
-        object_name.or(record_name).unwrap_or(ident_name)
-    )?;
+    writeln!(out, "ParameterName={}", object.name)?;
 
     // DataType=0xXXXX
     if let Some(typ) = object.typ {
-        writeln!(file, "DataType=0x{:04X}", typ as u8)?;
+        writeln!(out, "DataType=0x{:04X}", typ as u8)?;
+    }
+    // LowLimit=... and HighLimit=...
+    if let Some(min) = &object.low_limit {
+        writeln!(out, "LowLimit={min}")?;
+    }
+    if let Some(max) = &object.high_limit {
+        writeln!(out, "HighLimit={max}")?;
     }
     // AccessType=ro|wo|rw
-    writeln!(
-        file,
-        "AccessType={}",
-        if object.read_only {
-            "ro"
-        } else if object.write_only {
-            "wo"
-        } else {
-            "rw"
-        }
-    )?; // TODO const
+    writeln!(out, "AccessType={}", object.access_type)?;
+
+    // DefaultValue=...
+    if let Some(default) = &object.default {
+        writeln!(out, "DefaultValue={default}")?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use quote::format_ident;
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn test_nested_record() {
+        let record_field = Object::new_in_record(
+            &[parse_quote!(#[canopen(min = 1, default = 2)])],
+            format_ident!("speed"),
+            &parse_quote!(u8),
+            1,
+        )
+        .unwrap();
+        let nested = NestedEds {
+            index: 0x2100,
+            objects: vec![EdsObject::new(&record_field, None)],
+        };
+        let record = Record {
+            ident: format_ident!("motor"),
+            index: 0x2100,
+            name: "Motor".to_string(),
+            is_array: false,
+        };
+        let objects = [Object::highest_subindex(format_ident!("motor"), 0x2100)];
+
+        let mut eds = Vec::new();
+        write_eds_into(&mut eds, &objects, &[record], &[nested]).unwrap();
+        let eds = String::from_utf8(eds).unwrap();
+        let expected = "
+[2100]
+ParameterName=Motor
+ObjectType=0x09
+
+[2100sub0]
+ParameterName=Highest sub-index supported
+DataType=0x0005
+AccessType=ro
+
+[2100sub1]
+ParameterName=speed
+DataType=0x0005
+LowLimit=1
+AccessType=rw
+DefaultValue=2
+";
+        assert!(eds.ends_with(expected), "{eds}");
+    }
+}
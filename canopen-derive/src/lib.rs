@@ -6,10 +6,13 @@ use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use syn::*;
 
-use crate::object::{extract_field_info, Object, Record};
+use crate::object::{extract_field_info, NestedRecord, Object, Record};
 
+mod check;
 mod eds;
 mod object;
+mod record;
+mod value;
 
 #[proc_macro_derive(OdData, attributes(canopen))]
 pub fn derive_interactive(input: TokenStream) -> TokenStream {
@@ -19,6 +22,14 @@ pub fn derive_interactive(input: TokenStream) -> TokenStream {
         .into()
 }
 
+#[proc_macro_derive(OdValue, attributes(canopen))]
+pub fn derive_od_value(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    value::od_value_impl(&ast)
+        .unwrap_or_else(|e| e.write_errors())
+        .into()
+}
+
 fn od_data_impl(ast: &ItemStruct) -> Result<TokenStream2> {
     let struct_name = &ast.ident;
 
@@ -28,9 +39,8 @@ fn od_data_impl(ast: &ItemStruct) -> Result<TokenStream2> {
         return Err(Error::custom("struct must have named fields").with_span(ast));
     }
 
-    let (objects, records) = get_objects_and_records(ast)?;
-    let od_size = objects.len();
-
+    let mut eds_path = None;
+    let mut is_record = false;
     if let Some(top_level_attr) = ast
         .attrs
         .iter()
@@ -39,16 +49,87 @@ fn od_data_impl(ast: &ItemStruct) -> Result<TokenStream2> {
         top_level_attr.parse_nested_meta(|meta| {
             if meta.path.is_ident("eds_path") {
                 let path: LitStr = meta.value()?.parse()?;
-                eds::write_eds(std::path::Path::new(&path.value()), &objects, &records).unwrap()
+                eds_path = Some(path);
+            } else if meta.path.is_ident("record") {
+                is_record = true;
             }
             Ok(())
         })?;
     }
+    if is_record {
+        return record::record_impl(ast);
+    }
+
+    let (objects, records, nested) = get_objects_and_records(ast)?;
+    let od_size = objects.len();
+
+    // `OdRecord::LEN` can't be used in the const generics of a generic struct
+    if let Some(record) = nested.first() {
+        if !ast.generics.params.is_empty() {
+            return Err(
+                Error::custom("structs with nested records can't be generic")
+                    .with_span(&record.ident),
+            );
+        }
+    }
+
+    if let Some(path) = eds_path {
+        let nested_eds = nested
+            .iter()
+            .map(|record| {
+                // only the derive of the record itself knows its fields
+                let name = record_name(&record.ty);
+                let objects = name
+                    .as_deref()
+                    .and_then(eds::record_objects)
+                    .ok_or_else(|| {
+                        Error::custom(
+                            "the EDS file needs a record derived with #[canopen(record)] \
+                         earlier in the same crate",
+                        )
+                        .with_span(&record.ty)
+                    })?;
+                Ok(eds::NestedEds {
+                    index: record.index,
+                    objects,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        eds::write_eds(
+            std::path::Path::new(&path.value()),
+            &objects,
+            &records,
+            &nested_eds,
+        )
+        .unwrap()
+    }
+
+    if !nested.is_empty() {
+        return Ok(record::nested_od_impl(ast, &objects, &records, &nested));
+    }
 
     let indices = objects.iter().map(|v| v.index);
     let subindices = objects.iter().map(|v| v.subindex);
     let flags = objects.iter().map(Object::flags);
     let idents: Vec<_> = objects.iter().map(|v| &v.ident).collect();
+    let metadata = objects.iter().map(|object| {
+        let record = records.iter().find(|r| r.index == object.index);
+        object.metadata(record)
+    });
+    let limits = objects.iter().enumerate().filter_map(|(position, object)| {
+        let limits = object.limits()?;
+        Some(quote!((::canopen::objectdictionary::OdPosition(#position), #limits)))
+    });
+    let defaults = objects.iter().enumerate().filter_map(|(position, object)| {
+        let default = object.default_value()?;
+        Some(quote!((::canopen::objectdictionary::OdPosition(#position), &[#(#default),*])))
+    });
+
+    let (validated, validators): (Vec<_>, Vec<_>) = objects
+        .iter()
+        .enumerate()
+        .filter_map(|(position, object)| Some((position, object.validate.as_ref()?)))
+        .unzip();
 
     Ok(quote! {
         impl #impl_generics ::canopen::objectdictionary::OdData for #struct_name #ty_generics #where_clause {
@@ -62,6 +143,18 @@ fn od_data_impl(ast: &ItemStruct) -> Result<TokenStream2> {
                         [#(#flags),*],
                         [#(::core::mem::offset_of!(#struct_name #ty_generics, #idents)),*],
                         [#(::canopen::meta::metadata(&self.#idents as &dyn ::canopen::objectdictionary::datalink::DataLink)),*],
+                        &[#(#metadata),*],
+                        &[#(#limits),*],
+                        &[#(#defaults),*],
+                        |_data: &#struct_name #ty_generics, _position, _value| {
+                            match _position.0 {
+                                #(#validated => #validators(
+                                    _data,
+                                    ::canopen::objectdictionary::datalink::OdValue::from_bytes(_value)?,
+                                ),)*
+                                _ => Ok(()),
+                            }
+                        },
                         self,
                     )
                 }
@@ -70,12 +163,23 @@ fn od_data_impl(ast: &ItemStruct) -> Result<TokenStream2> {
     })
 }
 
+/// Name of the struct a nested record field has as its type
+fn record_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(path) => Some(path.path.segments.last()?.ident.to_string()),
+        _ => None,
+    }
+}
+
 /// convert fields to `Objects`s and `Record`s and sort/de-duplicate them
-fn get_objects_and_records(ast: &ItemStruct) -> Result<(Vec<Object>, Vec<Record>)> {
+fn get_objects_and_records(
+    ast: &ItemStruct,
+) -> Result<(Vec<Object>, Vec<Record>, Vec<NestedRecord>)> {
     let mut errors = Error::accumulator();
 
     let mut objects = Vec::new();
     let mut records = Vec::new();
+    let mut nested = Vec::new();
     for field_info in ast
         .fields
         .iter()
@@ -83,6 +187,21 @@ fn get_objects_and_records(ast: &ItemStruct) -> Result<(Vec<Object>, Vec<Record>
     {
         objects.extend(field_info.objects);
         records.extend(field_info.records);
+        if let Some(record) = field_info.nested {
+            objects.push(Object::highest_subindex(record.ident.clone(), record.index));
+            nested.push(record);
+        }
+    }
+    errors.finish()?;
+
+    // the subindices of a nested record are only known to the compiler
+    let mut errors = Error::accumulator();
+    for object in &objects {
+        if object.subindex != 0 && nested.iter().any(|r| r.index == object.index) {
+            errors.push(
+                Error::custom("index is already used by a nested record").with_span(&object.ident),
+            );
+        }
     }
     errors.finish()?;
 
@@ -90,7 +209,8 @@ fn get_objects_and_records(ast: &ItemStruct) -> Result<(Vec<Object>, Vec<Record>
     check_for_duplicates(&objects, |o| (o.index, o.subindex), |o| &o.ident)?;
     records.sort_unstable_by_key(|r| r.index);
     check_for_duplicates(&records, |r| r.index, |o| &o.ident)?;
-    Ok((objects, records))
+    check::check_objects(&objects, &records)?;
+    Ok((objects, records, nested))
 }
 
 // expects items to be sorted
@@ -2,7 +2,7 @@ use std::slice;
 
 use darling::{Error, FromAttributes, FromMeta, Result};
 use proc_macro2::{Ident, TokenStream};
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::*;
 
 pub fn extract_field_info(field: &Field) -> Result<FieldInfo> {
@@ -21,6 +21,9 @@ pub fn extract_field_info(field: &Field) -> Result<FieldInfo> {
                 is_array: false,
             });
         } else if let Ok(array) = ArrayParser::from_attributes(slice::from_ref(attr)) {
+            if let Err(e) = array.check_field(&field.ty) {
+                errors.push(e.with_span(attr));
+            }
             array.generate_objects(ident, &mut objects);
             records.push(Record {
                 ident: ident.clone(),
@@ -38,28 +41,61 @@ pub fn extract_field_info(field: &Field) -> Result<FieldInfo> {
     errors.finish()?;
 
     if objects.is_empty() {
+        // a struct with `#[canopen(record)]`, its fields are added by `OdRecord`
+        if let [record] = records.as_slice() {
+            if !record.is_array {
+                let nested = NestedRecord {
+                    ident: ident.clone(),
+                    ty: field.ty.clone(),
+                    index: record.index,
+                };
+                return Ok(FieldInfo {
+                    objects,
+                    records,
+                    nested: Some(nested),
+                });
+            }
+        }
         return Err(
             Error::custom("field must have at least one #[canopen()] attribute").with_span(field),
         );
     }
 
-    Ok(FieldInfo { objects, records })
+    Ok(FieldInfo {
+        objects,
+        records,
+        nested: None,
+    })
 }
 
 pub struct FieldInfo {
     pub objects: Vec<Object>,
     pub records: Vec<Record>,
+    pub nested: Option<NestedRecord>,
 }
 
-#[derive(Eq, Debug)]
+/// A field whose type derives `OdData` with `#[canopen(record)]`
+pub struct NestedRecord {
+    pub ident: Ident,
+    pub ty: Type,
+    pub index: u16,
+}
+
+#[derive(Debug)]
 pub struct Object {
     pub ident: Ident,
     pub index: u16,
     pub subindex: u8,
     pub read_only: bool,
     pub write_only: bool,
+    pub persist: bool,
     pub name: Option<String>,
     pub typ: Option<DataType>,
+    pub min: Option<Number>,
+    pub max: Option<Number>,
+    pub limits: Option<Limits>,
+    pub default: Option<DefaultValue>,
+    pub validate: Option<Path>,
 }
 
 #[derive(Eq, Debug)]
@@ -73,7 +109,8 @@ pub struct Record {
 #[derive(darling::FromAttributes)]
 #[darling(attributes(canopen))]
 struct ObjectParser {
-    index: u16,
+    #[darling(default)]
+    index: Option<u16>,
     #[darling(default)]
     subindex: Option<u8>,
     #[darling(default)]
@@ -81,32 +118,115 @@ struct ObjectParser {
     #[darling(default)]
     write_only: bool,
     #[darling(default)]
+    persist: bool,
+    #[darling(default)]
     name: Option<String>,
     #[darling(default, and_then = "Object::parse_datatype")]
     typ: Option<DataType>,
+    #[darling(default)]
+    min: Option<Number>,
+    #[darling(default)]
+    max: Option<Number>,
+    #[darling(default)]
+    default: Option<DefaultValue>,
+    #[darling(default)]
+    validate: Option<Path>,
 }
 
 impl Object {
     pub fn new(attr: &Attribute, ident: Ident, typ: &Type) -> Result<Self> {
         let object =
             ObjectParser::from_attributes(slice::from_ref(attr)).map_err(|e| e.with_span(attr))?;
+        let Some(index) = object.index else {
+            return Err(Error::missing_field("index").with_span(attr));
+        };
+        let subindex = object.subindex.unwrap_or(0);
+        Object::from_parser(object, ident, typ, index, subindex).map_err(|e| e.with_span(attr))
+    }
+
+    /// A field of a struct with `#[canopen(record)]`, `attrs` is empty or a single attribute.
+    ///
+    /// The index is set by the outer struct, the subindex defaults to `next_subindex`.
+    pub fn new_in_record(
+        attrs: &[Attribute],
+        ident: Ident,
+        typ: &Type,
+        next_subindex: u8,
+    ) -> Result<Self> {
+        let object = ObjectParser::from_attributes(attrs)?;
+        if object.index.is_some() {
+            return Err(Error::custom(
+                "the index of a record is set by the field of the outer struct",
+            ));
+        }
+        let subindex = object.subindex.unwrap_or(next_subindex);
+        if subindex == 0 {
+            return Err(Error::custom("subindex 0 of a record is generated"));
+        }
+        Object::from_parser(object, ident, typ, 0, subindex)
+    }
+
+    /// Subindex 0 of a nested record
+    pub fn highest_subindex(ident: Ident, index: u16) -> Self {
+        Object {
+            ident,
+            index,
+            subindex: 0,
+            read_only: true,
+            write_only: false,
+            persist: false,
+            name: Some("Highest sub-index supported".to_string()),
+            typ: Some(DataType::UNSIGNED8),
+            min: None,
+            max: None,
+            limits: None,
+            default: None,
+            validate: None,
+        }
+    }
+
+    fn from_parser(
+        object: ObjectParser,
+        ident: Ident,
+        typ: &Type,
+        index: u16,
+        subindex: u8,
+    ) -> Result<Self> {
         if object.read_only && object.write_only {
-            return Err(
-                Error::custom("Object cannot be both read-only and write-only").with_span(attr),
-            );
+            return Err(Error::custom(
+                "Object cannot be both read-only and write-only",
+            ));
         }
         let mut object = Object {
             ident,
-            index: object.index,
-            subindex: object.subindex.unwrap_or(0),
+            index,
+            subindex,
             read_only: object.read_only,
             write_only: object.write_only,
+            persist: object.persist,
             name: object.name,
             typ: object.typ,
+            min: object.min,
+            max: object.max,
+            limits: None,
+            default: object.default,
+            validate: object.validate,
         };
 
-        if object.typ.is_none() {
-            object.typ = Object::guess_type(typ);
+        match (object.typ, Object::guess_type(typ)) {
+            (None, guessed) => object.typ = guessed,
+            (Some(explicit), Some(guessed)) if !explicit.is_compatible(guessed) => {
+                return Err(Error::custom(format!(
+                    "`typ` is {explicit:?}, but the type of the field is {guessed:?}"
+                )));
+            }
+            _ => {}
+        }
+        if object.min.is_some() || object.max.is_some() {
+            object.limits = Some(Limits::new(object.typ, object.min, object.max)?);
+        }
+        if let Some(default) = &object.default {
+            default.encode(object.typ, object.limits)?;
         }
 
         Ok(object)
@@ -120,20 +240,96 @@ impl Object {
         if self.write_only {
             flags = quote!(#flags.set_write_only());
         }
+        if self.persist {
+            flags = quote!(#flags.set_persistent());
+        }
+        if self.validate.is_some() {
+            flags = quote!(#flags.set_validated());
+        }
+        if let Some(pdo_size) = self.typ.and_then(DataType::pdo_size) {
+            flags = quote!(#flags.set_pdo_size(::canopen::objectdictionary::object::PdoSize::#pdo_size));
+        }
         flags
     }
 
+    pub fn limits(&self) -> Option<TokenStream> {
+        let limits = match self.limits? {
+            Limits::Signed { min, max } => quote!(Signed { min: #min, max: #max }),
+            Limits::Unsigned { min, max } => quote!(Unsigned { min: #min, max: #max }),
+            Limits::Real { min, max } => quote!(Real { min: #min, max: #max }),
+        };
+        Some(quote!(::canopen::objectdictionary::object::Limits::#limits))
+    }
+
+    /// `ParameterName`, falling back to the name of the record and the field
+    pub fn parameter_name(&self, record: Option<&Record>) -> String {
+        let record_name = record.map(|r| r.name.clone());
+        let ident_name = self.ident.to_string();
+        self.name.clone().or(record_name).unwrap_or(ident_name)
+    }
+
+    pub fn metadata(&self, record: Option<&Record>) -> TokenStream {
+        let name = self.parameter_name(record);
+        let data_type = match self.typ {
+            Some(typ) => {
+                let typ = format_ident!("{typ:?}");
+                quote!(Some(::canopen::objectdictionary::object::DataType::#typ))
+            }
+            None => quote!(None),
+        };
+        quote!(::canopen::objectdictionary::object::ObjectMetadata {
+            name: #name,
+            data_type: #data_type,
+        })
+    }
+
+    /// The default value encoded as bytes
+    pub fn default_value(&self) -> Option<Vec<u8>> {
+        let default = self.default.as_ref()?;
+        Some(
+            default
+                .encode(self.typ, self.limits)
+                .expect("checked by Object::new"),
+        )
+    }
+
     fn parse_datatype(val: Expr) -> Result<Option<DataType>> {
         DataType::from_expr(&val).map(Some)
     }
 
     fn guess_type(ty: &Type) -> Option<DataType> {
         match ty {
-            Type::Path(TypePath { path, .. }) => DataType::from_rust_type(path),
+            Type::Path(TypePath { path, .. }) => {
+                DataType::from_rust_type(path).or_else(|| Self::guess_generic_type(path))
+            }
             Type::Reference(reference) => Self::guess_type(&reference.elem),
             _ => None,
         }
     }
+
+    // OdCell<T>, Domain<T>, heapless::String<N> and heapless::Vec<u8, N>
+    fn guess_generic_type(path: &Path) -> Option<DataType> {
+        let segment = path.segments.last()?;
+        if segment.ident == "Domain" {
+            return Some(DataType::DOMAIN);
+        }
+        if segment.ident == "String" {
+            return Some(DataType::VISIBLE_STRING);
+        }
+        if segment.ident == "Vec" {
+            return Some(DataType::OCTET_STRING);
+        }
+        if segment.ident != "OdCell" {
+            return None;
+        }
+        match &segment.arguments {
+            PathArguments::AngleBracketed(arguments) => match arguments.args.first()? {
+                GenericArgument::Type(ty) => Self::guess_type(ty),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 }
 
 #[derive(darling::FromAttributes)]
@@ -154,9 +350,65 @@ pub struct ArrayParser {
     pub read_only: bool,
     #[darling(default)]
     pub write_only: bool,
+    #[darling(default)]
+    pub persist: bool,
 }
 
 impl ArrayParser {
+    /// Compare `size` and `typ` with an `OdArray<T, N>` or `[T; N]` field
+    fn check_field(&self, ty: &Type) -> Result<()> {
+        let Some((element, len)) = ArrayParser::element_and_len(ty) else {
+            return Ok(());
+        };
+        if let Some(len) = len.filter(|&len| len != self.size as usize) {
+            return Err(Error::custom(format!(
+                "array size is {}, but the field holds {len} elements",
+                self.size
+            )));
+        }
+        if let Some(guessed) = Object::guess_type(element) {
+            if !self.typ.is_compatible(guessed) {
+                return Err(Error::custom(format!(
+                    "`typ` is {:?}, but the type of the elements is {guessed:?}",
+                    self.typ
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// `None` for the length if it isn't a literal
+    fn element_and_len(ty: &Type) -> Option<(&Type, Option<usize>)> {
+        let literal_len = |expr: &Expr| match expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(int), ..
+            }) => int.base10_parse().ok(),
+            _ => None,
+        };
+        match ty {
+            Type::Array(array) => Some((&array.elem, literal_len(&array.len))),
+            Type::Path(TypePath { path, .. }) => {
+                let segment = path.segments.last()?;
+                if segment.ident != "OdArray" {
+                    return None;
+                }
+                let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+                    return None;
+                };
+                let mut args = arguments.args.iter();
+                let Some(GenericArgument::Type(element)) = args.next() else {
+                    return None;
+                };
+                let len = match args.next() {
+                    Some(GenericArgument::Const(expr)) => literal_len(expr),
+                    _ => None,
+                };
+                Some((element, len))
+            }
+            _ => None,
+        }
+    }
+
     fn generate_objects(&self, ident: &Ident, objects: &mut Vec<Object>) {
         let array_len = Object {
             ident: ident.clone(),
@@ -164,8 +416,14 @@ impl ArrayParser {
             subindex: 0,
             read_only: true,
             write_only: false,
+            persist: false,
             name: None,
             typ: Some(DataType::UNSIGNED8),
+            min: None,
+            max: None,
+            limits: None,
+            default: None,
+            validate: None,
         };
         objects.push(array_len);
         for i in 1..=self.size {
@@ -175,8 +433,14 @@ impl ArrayParser {
                 subindex: i,
                 read_only: self.read_only,
                 write_only: self.write_only,
+                persist: self.persist,
                 name: None,
                 typ: Some(self.typ),
+                min: None,
+                max: None,
+                limits: None,
+                default: None,
+                validate: None,
             };
             objects.push(array_element);
         }
@@ -204,6 +468,8 @@ impl PartialEq for Object {
     }
 }
 
+impl Eq for Object {}
+
 // Implement Ord and PartialOrd for Record so we can sort them
 impl Ord for Record {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
@@ -223,6 +489,199 @@ impl PartialEq for Record {
     }
 }
 
+/// Integer or floating point literal of the `min` and `max` attributes
+#[derive(Copy, Clone, Debug)]
+pub enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+impl FromMeta for Number {
+    fn from_expr(expr: &Expr) -> Result<Self> {
+        match expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(int), ..
+            }) => Ok(Number::Int(int.base10_parse()?)),
+            Expr::Lit(ExprLit {
+                lit: Lit::Float(float),
+                ..
+            }) => Ok(Number::Float(float.base10_parse()?)),
+            Expr::Unary(ExprUnary {
+                op: UnOp::Neg(_),
+                expr,
+                ..
+            }) => match Number::from_expr(expr)? {
+                Number::Int(int) => Ok(Number::Int(-int)),
+                Number::Float(float) => Ok(Number::Float(-float)),
+            },
+            _ => Err(Error::unexpected_expr_type(expr)),
+        }
+    }
+}
+
+impl std::fmt::Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Number::Int(int) => write!(f, "{int}"),
+            Number::Float(float) => write!(f, "{float}"),
+        }
+    }
+}
+
+/// Literal of the `default` attribute
+#[derive(Clone, Debug)]
+pub enum DefaultValue {
+    Number(Number),
+    Bool(bool),
+    String(String),
+}
+
+impl FromMeta for DefaultValue {
+    fn from_expr(expr: &Expr) -> Result<Self> {
+        match expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Bool(bool),
+                ..
+            }) => Ok(DefaultValue::Bool(bool.value)),
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(string),
+                ..
+            }) => Ok(DefaultValue::String(string.value())),
+            _ => Number::from_expr(expr).map(DefaultValue::Number),
+        }
+    }
+}
+
+impl std::fmt::Display for DefaultValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DefaultValue::Number(number) => write!(f, "{number}"),
+            DefaultValue::Bool(bool) => write!(f, "{}", *bool as u8),
+            DefaultValue::String(string) => write!(f, "{string}"),
+        }
+    }
+}
+
+impl DefaultValue {
+    /// Little-endian encoding as written to the object when restoring defaults
+    fn encode(&self, typ: Option<DataType>, limits: Option<Limits>) -> Result<Vec<u8>> {
+        let Some(typ) = typ else {
+            return Err(Error::custom("`default` requires a data type"));
+        };
+        match self {
+            DefaultValue::Bool(bool) if typ == DataType::BOOLEAN => Ok(vec![*bool as u8]),
+            DefaultValue::String(string)
+                if matches!(typ, DataType::VISIBLE_STRING | DataType::OCTET_STRING) =>
+            {
+                Ok(string.as_bytes().to_vec())
+            }
+            DefaultValue::Number(number) if typ.range().is_some() => {
+                // checks the range of the data type
+                let value = Limits::new(Some(typ), Some(*number), Some(*number))?;
+                let size = typ.size().unwrap();
+                let in_limits = match (value, limits) {
+                    (_, None) => true,
+                    (Limits::Signed { min: value, .. }, Some(Limits::Signed { min, max })) => {
+                        (min..=max).contains(&value)
+                    }
+                    (Limits::Unsigned { min: value, .. }, Some(Limits::Unsigned { min, max })) => {
+                        (min..=max).contains(&value)
+                    }
+                    (Limits::Real { min: value, .. }, Some(Limits::Real { min, max })) => {
+                        (min..=max).contains(&value)
+                    }
+                    _ => unreachable!("limits match the data type"),
+                };
+                if !in_limits {
+                    return Err(Error::custom(
+                        "`default` is out of the limits of the object",
+                    ));
+                }
+                Ok(match value {
+                    Limits::Signed { min: value, .. } => value.to_le_bytes()[..size].to_vec(),
+                    Limits::Unsigned { min: value, .. } => value.to_le_bytes()[..size].to_vec(),
+                    Limits::Real { min: value, .. } if size == 4 => {
+                        (value as f32).to_le_bytes().to_vec()
+                    }
+                    Limits::Real { min: value, .. } => value.to_le_bytes().to_vec(),
+                })
+            }
+            _ => Err(Error::custom("`default` does not match the data type")),
+        }
+    }
+}
+
+/// Range of values accepted on writes, filled up with the range of the data type
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Limits {
+    Signed { min: i64, max: i64 },
+    Unsigned { min: u64, max: u64 },
+    Real { min: f64, max: f64 },
+}
+
+impl Limits {
+    fn new(typ: Option<DataType>, min: Option<Number>, max: Option<Number>) -> Result<Self> {
+        let Some(range) = typ.and_then(DataType::range) else {
+            return Err(Error::custom("`min` and `max` require a numeric data type"));
+        };
+        let out_of_range = || Error::custom("limit is out of the range of the data type");
+        let limits = match range {
+            Limits::Signed { min: lo, max: hi } => {
+                let limit = |number, default| match number {
+                    None => Ok(default),
+                    Some(Number::Int(int)) => i64::try_from(int)
+                        .ok()
+                        .filter(|int| (lo..=hi).contains(int))
+                        .ok_or_else(out_of_range),
+                    Some(Number::Float(_)) => Err(Error::custom("expected an integer")),
+                };
+                Limits::Signed {
+                    min: limit(min, lo)?,
+                    max: limit(max, hi)?,
+                }
+            }
+            Limits::Unsigned { min: lo, max: hi } => {
+                let limit = |number, default| match number {
+                    None => Ok(default),
+                    Some(Number::Int(int)) => u64::try_from(int)
+                        .ok()
+                        .filter(|int| (lo..=hi).contains(int))
+                        .ok_or_else(out_of_range),
+                    Some(Number::Float(_)) => Err(Error::custom("expected an integer")),
+                };
+                Limits::Unsigned {
+                    min: limit(min, lo)?,
+                    max: limit(max, hi)?,
+                }
+            }
+            Limits::Real { min: lo, max: hi } => {
+                let limit = |number, default| match number {
+                    None => Ok(default),
+                    Some(Number::Int(int)) => Some(int as f64)
+                        .filter(|float| (lo..=hi).contains(float))
+                        .ok_or_else(out_of_range),
+                    Some(Number::Float(float)) => Some(float)
+                        .filter(|float| (lo..=hi).contains(float))
+                        .ok_or_else(out_of_range),
+                };
+                Limits::Real {
+                    min: limit(min, lo)?,
+                    max: limit(max, hi)?,
+                }
+            }
+        };
+        let max_less_than_min = match limits {
+            Limits::Signed { min, max } => max < min,
+            Limits::Unsigned { min, max } => max < min,
+            Limits::Real { min, max } => max < min,
+        };
+        if max_less_than_min {
+            return Err(Error::custom("`max` is less than `min`"));
+        }
+        Ok(limits)
+    }
+}
+
 /// Taken from CiA 301, Table 44: Object dictionary data types
 #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -265,25 +724,108 @@ impl FromMeta for DataType {
     fn from_expr(expr: &Expr) -> Result<Self> {
         match expr {
             Expr::Lit(ExprLit { lit, .. }) => {
-                let num = u8::from_value(&lit)?;
+                let num = u8::from_value(lit)?;
                 match DataType::from_u8(num) {
                     Some(typ) => Ok(typ),
                     None => Err(Error::custom("invalid data type")),
                 }
             }
-            Expr::Path(ExprPath { path, .. }) => match DataType::from_rust_type(&path) {
+            Expr::Path(ExprPath { path, .. }) => match DataType::from_rust_type(path) {
                 Some(typ) => Ok(typ),
-                None => match DataType::from_str(&path) {
+                None => match DataType::from_str(path) {
                     Some(typ) => Ok(typ),
                     None => Err(Error::custom("invalid data type")),
                 },
             },
-            _ => Err(Error::unexpected_expr_type(&expr)),
+            _ => Err(Error::unexpected_expr_type(expr)),
         }
     }
 }
 
 impl DataType {
+    /// Whether a field of type `other` can be declared as `self`
+    pub fn is_compatible(self, other: DataType) -> bool {
+        let is_string = |typ| {
+            matches!(
+                typ,
+                DataType::VISIBLE_STRING
+                    | DataType::OCTET_STRING
+                    | DataType::UNICODE_STRING
+                    | DataType::DOMAIN
+            )
+        };
+        self == other || (is_string(self) && is_string(other))
+    }
+
+    /// Name of the matching `PdoSize` variant for types that can be mapped into a PDO
+    fn pdo_size(self) -> Option<Ident> {
+        let name = match self {
+            DataType::BOOLEAN | DataType::INTEGER8 | DataType::UNSIGNED8 => "One",
+            DataType::INTEGER16 | DataType::UNSIGNED16 => "Two",
+            DataType::INTEGER24 | DataType::UNSIGNED24 => "Three",
+            DataType::INTEGER32 | DataType::UNSIGNED32 | DataType::REAL32 => "Four",
+            DataType::INTEGER40 | DataType::UNSIGNED40 => "Five",
+            DataType::INTEGER48 | DataType::UNSIGNED48 => "Six",
+            DataType::INTEGER56 | DataType::UNSIGNED56 => "Seven",
+            DataType::INTEGER64 | DataType::UNSIGNED64 | DataType::REAL64 => "Eight",
+            _ => return None,
+        };
+        Some(Ident::new(name, proc_macro2::Span::call_site()))
+    }
+
+    /// Size in bytes of numeric data types
+    fn size(self) -> Option<usize> {
+        match self {
+            DataType::BOOLEAN | DataType::INTEGER8 | DataType::UNSIGNED8 => Some(1),
+            DataType::INTEGER16 | DataType::UNSIGNED16 => Some(2),
+            DataType::INTEGER24 | DataType::UNSIGNED24 => Some(3),
+            DataType::INTEGER32 | DataType::UNSIGNED32 | DataType::REAL32 => Some(4),
+            DataType::INTEGER40 | DataType::UNSIGNED40 => Some(5),
+            DataType::INTEGER48 | DataType::UNSIGNED48 => Some(6),
+            DataType::INTEGER56 | DataType::UNSIGNED56 => Some(7),
+            DataType::INTEGER64 | DataType::UNSIGNED64 | DataType::REAL64 => Some(8),
+            _ => None,
+        }
+    }
+
+    fn range(self) -> Option<Limits> {
+        let signed = |bits: u32| Limits::Signed {
+            min: i64::MIN >> (64 - bits),
+            max: i64::MAX >> (64 - bits),
+        };
+        let unsigned = |bits: u32| Limits::Unsigned {
+            min: 0,
+            max: u64::MAX >> (64 - bits),
+        };
+        match self {
+            DataType::INTEGER8 => Some(signed(8)),
+            DataType::INTEGER16 => Some(signed(16)),
+            DataType::INTEGER24 => Some(signed(24)),
+            DataType::INTEGER32 => Some(signed(32)),
+            DataType::INTEGER40 => Some(signed(40)),
+            DataType::INTEGER48 => Some(signed(48)),
+            DataType::INTEGER56 => Some(signed(56)),
+            DataType::INTEGER64 => Some(signed(64)),
+            DataType::UNSIGNED8 => Some(unsigned(8)),
+            DataType::UNSIGNED16 => Some(unsigned(16)),
+            DataType::UNSIGNED24 => Some(unsigned(24)),
+            DataType::UNSIGNED32 => Some(unsigned(32)),
+            DataType::UNSIGNED40 => Some(unsigned(40)),
+            DataType::UNSIGNED48 => Some(unsigned(48)),
+            DataType::UNSIGNED56 => Some(unsigned(56)),
+            DataType::UNSIGNED64 => Some(unsigned(64)),
+            DataType::REAL32 => Some(Limits::Real {
+                min: f32::MIN as f64,
+                max: f32::MAX as f64,
+            }),
+            DataType::REAL64 => Some(Limits::Real {
+                min: f64::MIN,
+                max: f64::MAX,
+            }),
+            _ => None,
+        }
+    }
+
     fn from_u8(val: u8) -> Option<DataType> {
         match val {
             0x1 => Some(DataType::BOOLEAN),
@@ -400,6 +942,28 @@ impl DataType {
             Some(DataType::UNSIGNED32)
         } else if path.is_ident("f32") {
             Some(DataType::REAL32)
+        } else if path.is_ident("i64") {
+            Some(DataType::INTEGER64)
+        } else if path.is_ident("u64") {
+            Some(DataType::UNSIGNED64)
+        } else if path.is_ident("f64") {
+            Some(DataType::REAL64)
+        } else if path.is_ident("I24") {
+            Some(DataType::INTEGER24)
+        } else if path.is_ident("I40") {
+            Some(DataType::INTEGER40)
+        } else if path.is_ident("I48") {
+            Some(DataType::INTEGER48)
+        } else if path.is_ident("I56") {
+            Some(DataType::INTEGER56)
+        } else if path.is_ident("U24") {
+            Some(DataType::UNSIGNED24)
+        } else if path.is_ident("U40") {
+            Some(DataType::UNSIGNED40)
+        } else if path.is_ident("U48") {
+            Some(DataType::UNSIGNED48)
+        } else if path.is_ident("U56") {
+            Some(DataType::UNSIGNED56)
         } else if path.is_ident("str") {
             Some(DataType::OCTET_STRING)
         } else {
@@ -437,4 +1001,82 @@ mod tests {
                 .expect("Failed to parse attribute");
         assert_eq!(object.typ, Some(DataType::BOOLEAN));
     }
+
+    #[test]
+    fn test_guess_type() {
+        assert_eq!(
+            Object::guess_type(&parse_quote!(OdCell<heapless::String<16>>)),
+            Some(DataType::VISIBLE_STRING)
+        );
+        assert_eq!(
+            Object::guess_type(&parse_quote!(OdCell<heapless::Vec<u8, 16>>)),
+            Some(DataType::OCTET_STRING)
+        );
+        assert_eq!(
+            Object::guess_type(&parse_quote!(OdCell<u32>)),
+            Some(DataType::UNSIGNED32)
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = Limits::new(Some(DataType::INTEGER24), Some(Number::Int(-5)), None);
+        assert_eq!(
+            limits.ok(),
+            Some(Limits::Signed {
+                min: -5,
+                max: 0x7F_FFFF
+            })
+        );
+        let limits = Limits::new(Some(DataType::REAL32), None, Some(Number::Int(3)));
+        assert_eq!(
+            limits.ok(),
+            Some(Limits::Real {
+                min: f32::MIN as f64,
+                max: 3.0
+            })
+        );
+        assert!(Limits::new(Some(DataType::UNSIGNED8), Some(Number::Int(-1)), None).is_err());
+        assert!(Limits::new(Some(DataType::UNSIGNED8), None, Some(Number::Int(256))).is_err());
+        assert!(Limits::new(Some(DataType::UNSIGNED8), Some(Number::Float(1.0)), None).is_err());
+        assert!(Limits::new(Some(DataType::BOOLEAN), Some(Number::Int(0)), None).is_err());
+        assert!(Limits::new(
+            Some(DataType::INTEGER8),
+            Some(Number::Int(1)),
+            Some(Number::Int(0))
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_default_value() {
+        let encode = |value, typ| DefaultValue::encode(&value, Some(typ), None).ok();
+        assert_eq!(
+            encode(DefaultValue::Number(Number::Int(-2)), DataType::INTEGER24),
+            Some(vec![0xFE, 0xFF, 0xFF])
+        );
+        assert_eq!(
+            encode(DefaultValue::Number(Number::Float(0.5)), DataType::REAL32),
+            Some(0.5f32.to_le_bytes().to_vec())
+        );
+        assert_eq!(
+            encode(DefaultValue::Bool(true), DataType::BOOLEAN),
+            Some(vec![1])
+        );
+        assert_eq!(
+            encode(DefaultValue::String("abc".into()), DataType::VISIBLE_STRING),
+            Some(b"abc".to_vec())
+        );
+        assert_eq!(
+            encode(DefaultValue::Number(Number::Int(256)), DataType::UNSIGNED8),
+            None
+        );
+        assert_eq!(encode(DefaultValue::Bool(true), DataType::UNSIGNED8), None);
+
+        let limits = Limits::Unsigned { min: 1, max: 10 };
+        let value = DefaultValue::Number(Number::Int(0));
+        assert!(value
+            .encode(Some(DataType::UNSIGNED8), Some(limits))
+            .is_err());
+    }
 }
@@ -0,0 +1,276 @@
+use darling::{Error, Result};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::*;
+
+use crate::eds::{self, EdsObject};
+use crate::object::{NestedRecord, Object, Record};
+
+/// `#[canopen(record)]`: implement `OdRecord` so the struct can be nested in another one
+pub fn record_impl(ast: &ItemStruct) -> Result<TokenStream> {
+    let struct_name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let mut errors = Error::accumulator();
+    let mut objects = Vec::new();
+    let mut next_subindex = 1;
+    for field in &ast.fields {
+        let ident = field.ident.clone().expect("field should have name");
+        let attrs: Vec<_> = field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("canopen"))
+            .cloned()
+            .collect();
+        if let Some(attr) = attrs.get(1) {
+            errors.push(
+                Error::custom("a field of a record takes a single #[canopen()] attribute")
+                    .with_span(attr),
+            );
+            continue;
+        }
+        match Object::new_in_record(&attrs, ident, &field.ty, next_subindex) {
+            Ok(object) => {
+                next_subindex = object.subindex.wrapping_add(1);
+                objects.push(object);
+            }
+            Err(e) => match attrs.first() {
+                Some(attr) => errors.push(e.with_span(attr)),
+                None => errors.push(e.with_span(field)),
+            },
+        }
+    }
+    if ast.fields.is_empty() {
+        errors.push(Error::custom("record must have at least one field").with_span(ast));
+    }
+    errors.finish()?;
+
+    objects.sort_unstable_by_key(|o| o.subindex);
+    crate::check_for_duplicates(&objects, |o| o.subindex, |o| &o.ident)?;
+    eds::register_record(
+        struct_name.to_string(),
+        objects.iter().map(|o| EdsObject::new(o, None)).collect(),
+    );
+
+    let len = objects.len();
+    let highest_subindex = objects.last().map_or(0, |o| o.subindex);
+    let subindices = objects.iter().map(|v| v.subindex);
+    let flags = objects.iter().map(Object::flags);
+    let idents: Vec<_> = objects.iter().map(|v| &v.ident).collect();
+    let metadata = objects.iter().map(|object| object.metadata(None));
+    let limits = objects.iter().enumerate().filter_map(|(position, object)| {
+        let limits = object.limits()?;
+        Some(quote!((#position, #limits)))
+    });
+    let defaults = objects.iter().enumerate().filter_map(|(position, object)| {
+        let default = object.default_value()?;
+        Some(quote!((#position, &[#(#default),*])))
+    });
+    let (validated, validators): (Vec<_>, Vec<_>) = objects
+        .iter()
+        .enumerate()
+        .filter_map(|(position, object)| Some((position, object.validate.as_ref()?)))
+        .unzip();
+
+    Ok(quote! {
+        impl #impl_generics ::canopen::objectdictionary::datalink::BasicData for #struct_name #ty_generics #where_clause {
+            fn read(
+                &mut self,
+                _: u16,
+                _: u8,
+            ) -> Result<::canopen::objectdictionary::datalink::BasicReadData, ::canopen::objectdictionary::ODError> {
+                Ok(#highest_subindex.into())
+            }
+
+            fn write(
+                &mut self,
+                _: ::canopen::objectdictionary::datalink::BasicWriteData,
+                _: ::canopen::objectdictionary::OdInfo,
+            ) -> Result<(), ::canopen::objectdictionary::ODError> {
+                Err(::canopen::objectdictionary::ODError::ReadOnlyError)
+            }
+        }
+
+        impl #impl_generics ::canopen::objectdictionary::record::OdRecord for #struct_name #ty_generics #where_clause {
+            const LEN: usize = #len;
+            const SUBINDICES: &'static [u8] = &[#(#subindices),*];
+            const FLAGS: &'static [::canopen::objectdictionary::object::ObjectFlags] = &[#(#flags),*];
+            const OFFSETS: &'static [usize] = &[#(::core::mem::offset_of!(#struct_name #ty_generics, #idents)),*];
+            const METADATA: &'static [::canopen::objectdictionary::object::ObjectMetadata] = &[#(#metadata),*];
+            const LIMITS: &'static [(usize, ::canopen::objectdictionary::object::Limits)] = &[#(#limits),*];
+            const DEFAULTS: &'static [(usize, &'static [u8])] = &[#(#defaults),*];
+
+            fn vtable(&self, position: usize) -> ::canopen::meta::DynMetadata<dyn ::canopen::objectdictionary::datalink::DataLink> {
+                [#(::canopen::meta::metadata(&self.#idents as &dyn ::canopen::objectdictionary::datalink::DataLink)),*][position]
+            }
+
+            fn validate(&self, _position: usize, _value: &[u8]) -> Result<(), ::canopen::objectdictionary::ODError> {
+                match _position {
+                    #(#validated => #validators(
+                        self,
+                        ::canopen::objectdictionary::datalink::OdValue::from_bytes(_value)?,
+                    ),)*
+                    _ => Ok(()),
+                }
+            }
+        }
+    })
+}
+
+/// `OdData` for a struct with fields that are records themselves
+///
+/// The positions of the objects depend on the `OdRecord::LEN` of the records before them,
+/// so the tables are filled by the generated code instead of being written out.
+pub fn nested_od_impl(
+    ast: &ItemStruct,
+    objects: &[Object],
+    records: &[Record],
+    nested: &[NestedRecord],
+) -> TokenStream {
+    let struct_name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let data = quote!(#struct_name #ty_generics);
+
+    let mut fill = Vec::new();
+    let mut metadata = Vec::new();
+    let mut limits = Vec::new();
+    let mut limits_len = Vec::new();
+    let mut defaults = Vec::new();
+    let mut defaults_len = Vec::new();
+    let mut validators = Vec::new();
+    // `OdRecord::LEN` of the nested records seen so far
+    let mut record_lens = Vec::new();
+    let mut count = 0usize;
+    for object in objects {
+        let position = quote!(#count #(+ #record_lens)*);
+        count += 1;
+
+        let index = object.index;
+        let subindex = object.subindex;
+        let flags = object.flags();
+        let ident = &object.ident;
+        fill.push(quote! {
+            indices[n] = #index;
+            subindices[n] = #subindex;
+            flags[n] = #flags;
+            offsets[n] = ::core::mem::offset_of!(#data, #ident);
+            vtables[n] = ::canopen::meta::metadata(&self.#ident as &dyn ::canopen::objectdictionary::datalink::DataLink);
+            n += 1;
+        });
+        let record = records.iter().find(|r| r.index == object.index);
+        let object_metadata = object.metadata(record);
+        metadata.push(quote!(table.extend(&[#object_metadata]);));
+        if let Some(object_limits) = object.limits() {
+            limits.push(quote! {
+                table.extend(&[(::canopen::objectdictionary::OdPosition(#position), #object_limits)]);
+            });
+            limits_len.push(quote!(1));
+        }
+        if let Some(default) = object.default_value() {
+            defaults.push(quote! {
+                table.extend(&[(::canopen::objectdictionary::OdPosition(#position), &[#(#default),*] as &[u8])]);
+            });
+            defaults_len.push(quote!(1));
+        }
+        if let Some(validator) = &object.validate {
+            validators.push(quote! {
+                if _position.0 == #position {
+                    return #validator(_data, ::canopen::objectdictionary::datalink::OdValue::from_bytes(_value)?);
+                }
+            });
+        }
+
+        // the subindices of a nested record follow its subindex 0
+        let Some(nested) = nested
+            .iter()
+            .find(|r| r.index == object.index && object.subindex == 0)
+        else {
+            continue;
+        };
+        let ty = &nested.ty;
+        let record = quote!(<#ty as ::canopen::objectdictionary::record::OdRecord>);
+        let start = quote!(#count #(+ #record_lens)*);
+        fill.push(quote! {
+            for i in 0..#record::LEN {
+                indices[n] = #index;
+                subindices[n] = #record::SUBINDICES[i];
+                flags[n] = #record::FLAGS[i];
+                offsets[n] = ::core::mem::offset_of!(#data, #ident) + #record::OFFSETS[i];
+                vtables[n] = #record::vtable(&self.#ident, i);
+                n += 1;
+            }
+        });
+        metadata.push(quote!(table.extend(#record::METADATA);));
+        limits.push(quote!(table.extend_record(#record::LIMITS, #start);));
+        limits_len.push(quote!(#record::LIMITS.len()));
+        defaults.push(quote!(table.extend_record(#record::DEFAULTS, #start);));
+        defaults_len.push(quote!(#record::DEFAULTS.len()));
+        validators.push(quote! {
+            if _position.0 >= #start && _position.0 < #start + #record::LEN {
+                return #record::validate(&_data.#ident, _position.0 - #start, _value);
+            }
+        });
+        record_lens.push(quote!(#record::LEN));
+    }
+    let od_size = quote!(#count #(+ #record_lens)*);
+    let first = &objects[0].ident;
+
+    quote! {
+        impl #impl_generics ::canopen::objectdictionary::OdData for #struct_name #ty_generics #where_clause {
+            type OdType = ::canopen::objectdictionary::ObjectDictionary<#data, { #od_size }>;
+
+            fn into_od(self) -> Self::OdType {
+                const N: usize = #od_size;
+                let mut indices = [0; N];
+                let mut subindices = [0; N];
+                let mut flags = [::canopen::objectdictionary::object::ObjectFlags::empty(); N];
+                let mut offsets = [0; N];
+                let mut vtables = [::canopen::meta::metadata(&self.#first as &dyn ::canopen::objectdictionary::datalink::DataLink); N];
+                let mut n = 0;
+                #(#fill)*
+                debug_assert_eq!(n, N);
+
+                unsafe {
+                    ::canopen::objectdictionary::ObjectDictionary::new(
+                        indices,
+                        subindices,
+                        flags,
+                        offsets,
+                        vtables,
+                        &const {
+                            let mut table = ::canopen::objectdictionary::record::Table::new(
+                                ::canopen::objectdictionary::object::ObjectMetadata {
+                                    name: "",
+                                    data_type: None,
+                                },
+                            );
+                            #(#metadata)*
+                            table.finish()
+                        },
+                        &const {
+                            let mut table = ::canopen::objectdictionary::record::Table::<(::canopen::objectdictionary::OdPosition, ::canopen::objectdictionary::object::Limits), { 0 #(+ #limits_len)* }>::new((
+                                ::canopen::objectdictionary::OdPosition(0),
+                                ::canopen::objectdictionary::object::Limits::Unsigned { min: 0, max: 0 },
+                            ));
+                            #(#limits)*
+                            table.finish()
+                        },
+                        &const {
+                            let mut table = ::canopen::objectdictionary::record::Table::<(::canopen::objectdictionary::OdPosition, &'static [u8]), { 0 #(+ #defaults_len)* }>::new((
+                                ::canopen::objectdictionary::OdPosition(0),
+                                &[],
+                            ));
+                            #(#defaults)*
+                            table.finish()
+                        },
+                        |_data: &#data, _position, _value| {
+                            #(#validators)*
+                            Ok(())
+                        },
+                        self,
+                    )
+                }
+            }
+        }
+    }
+}
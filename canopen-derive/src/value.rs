@@ -0,0 +1,162 @@
+use darling::{Error, FromAttributes, Result};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::*;
+
+/// Integer types an enum or a newtype can be stored as
+const INTEGERS: [&str; 8] = ["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64"];
+
+#[derive(darling::FromAttributes)]
+#[darling(attributes(canopen))]
+struct NewtypeParser {
+    /// Bits that must not be set
+    #[darling(default)]
+    reserved: Option<u64>,
+}
+
+/// `OdValue` and `BasicData` for `#[repr(uN)]` enums and integer newtypes
+pub fn od_value_impl(ast: &DeriveInput) -> Result<TokenStream> {
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let (repr, to_raw, from_raw) = match &ast.data {
+        Data::Enum(data) => enum_conversions(ast, data)?,
+        Data::Struct(data) => newtype_conversions(ast, data)?,
+        Data::Union(_) => {
+            return Err(Error::custom("unions are not supported").with_span(&ast.ident));
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::core::convert::From<#name #ty_generics> for ::canopen::objectdictionary::datalink::BasicReadData #where_clause {
+            fn from(val: #name #ty_generics) -> Self {
+                let val = &val;
+                ::canopen::objectdictionary::datalink::BasicReadData::from(#to_raw)
+            }
+        }
+
+        impl #impl_generics ::canopen::objectdictionary::datalink::OdValue for #name #ty_generics #where_clause {
+            fn from_bytes(bytes: &[u8]) -> Result<Self, ::canopen::objectdictionary::ODError> {
+                let raw = <#repr as ::canopen::objectdictionary::datalink::OdValue>::from_bytes(bytes)?;
+                #from_raw
+            }
+        }
+
+        impl #impl_generics ::canopen::objectdictionary::datalink::BasicData for #name #ty_generics #where_clause {
+            fn read(
+                &mut self,
+                _: u16,
+                _: u8,
+            ) -> Result<::canopen::objectdictionary::datalink::BasicReadData, ::canopen::objectdictionary::ODError> {
+                let val = &*self;
+                Ok(::canopen::objectdictionary::datalink::BasicReadData::from(#to_raw))
+            }
+
+            fn write(
+                &mut self,
+                data: ::canopen::objectdictionary::datalink::BasicWriteData,
+                _: ::canopen::objectdictionary::OdInfo,
+            ) -> Result<(), ::canopen::objectdictionary::ODError> {
+                let raw = <#repr>::try_from(data)?;
+                *self = <Self as ::canopen::objectdictionary::datalink::OdValue>::from_bytes(&raw.to_le_bytes())?;
+                Ok(())
+            }
+        }
+    })
+}
+
+/// A fieldless enum with `#[repr(uN)]`, unknown discriminants are `InvalidValue`
+fn enum_conversions(
+    ast: &DeriveInput,
+    data: &DataEnum,
+) -> Result<(Ident, TokenStream, TokenStream)> {
+    let mut repr = None;
+    for attr in ast.attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if let Some(ident) = meta.path.get_ident() {
+                if INTEGERS.iter().any(|integer| ident == integer) {
+                    repr = Some(ident.clone());
+                }
+            }
+            Ok(())
+        })?;
+    }
+    let Some(repr) = repr else {
+        return Err(
+            Error::custom("enum must have an integer representation like #[repr(u8)]")
+                .with_span(&ast.ident),
+        );
+    };
+
+    let mut errors = Error::accumulator();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            errors.push(Error::custom("variant must not have fields").with_span(variant));
+        }
+    }
+    errors.finish()?;
+
+    let name = &ast.ident;
+    let variants: Vec<_> = data.variants.iter().map(|v| &v.ident).collect();
+    let to_raw = quote! {
+        match val {
+            #(#name::#variants => #name::#variants as #repr,)*
+        }
+    };
+    let from_raw = quote! {
+        #(if raw == Self::#variants as #repr {
+            return Ok(Self::#variants);
+        })*
+        Err(::canopen::objectdictionary::ODError::InvalidValue)
+    };
+    Ok((repr, to_raw, from_raw))
+}
+
+/// A tuple struct with a single integer field, values with `reserved` bits are `InvalidValue`
+fn newtype_conversions(
+    ast: &DeriveInput,
+    data: &DataStruct,
+) -> Result<(Ident, TokenStream, TokenStream)> {
+    let repr = match &data.fields {
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => match &fields.unnamed[0].ty {
+            Type::Path(TypePath { path, .. }) => path
+                .get_ident()
+                .filter(|ident| INTEGERS.iter().any(|integer| *ident == integer))
+                .cloned(),
+            _ => None,
+        },
+        _ => None,
+    };
+    let Some(repr) = repr else {
+        return Err(Error::custom(
+            "struct must be a newtype of an integer like `struct Flags(u16)`",
+        )
+        .with_span(&ast.ident));
+    };
+
+    let attrs = NewtypeParser::from_attributes(&ast.attrs)?;
+    let bits = repr.to_string()[1..].parse::<u32>().expect("checked above");
+    if let Some(reserved) = attrs
+        .reserved
+        .filter(|reserved| reserved.checked_shr(bits) > Some(0))
+    {
+        return Err(Error::custom(format!(
+            "reserved bits 0x{reserved:X} don't fit into {repr}"
+        ))
+        .with_span(&ast.ident));
+    }
+    let check = attrs.reserved.map(|reserved| {
+        quote! {
+            if (raw as u64) & #reserved != 0 {
+                return Err(::canopen::objectdictionary::ODError::InvalidValue);
+            }
+        }
+    });
+
+    let to_raw = quote!(val.0);
+    let from_raw = quote! {
+        #check
+        Ok(Self(raw))
+    };
+    Ok((repr, to_raw, from_raw))
+}
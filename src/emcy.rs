@@ -0,0 +1,46 @@
+use embedded_can::StandardId;
+
+use crate::{EmcyMessage, NodeId};
+
+/// Error code signalling that all errors have been resolved
+pub const ERROR_RESET: u16 = 0x0000;
+
+/// Error register (object 0x1001) bits
+pub const GENERIC_ERROR: u8 = 1 << 0;
+pub const CURRENT_ERROR: u8 = 1 << 1;
+pub const VOLTAGE_ERROR: u8 = 1 << 2;
+pub const TEMPERATURE_ERROR: u8 = 1 << 3;
+pub const COMMUNICATION_ERROR: u8 = 1 << 4;
+pub const DEVICE_PROFILE_SPECIFIC_ERROR: u8 = 1 << 5;
+pub const MANUFACTURER_SPECIFIC_ERROR: u8 = 1 << 7;
+
+/// EMCY producer
+pub struct Emcy {
+    pub cob_id: StandardId,
+}
+
+impl Emcy {
+    pub fn new(node_id: NodeId) -> Self {
+        Emcy {
+            cob_id: node_id.emcy_cobid(),
+        }
+    }
+
+    /// Use the default EMCY COB-ID of another node-ID
+    pub fn set_node_id(&mut self, node_id: NodeId) {
+        self.cob_id = node_id.emcy_cobid();
+    }
+
+    pub fn message(
+        &self,
+        error_code: u16,
+        error_register: u8,
+        manufacturer_specific: [u8; 5],
+    ) -> EmcyMessage {
+        let mut data = [0; 8];
+        data[0..2].copy_from_slice(&error_code.to_le_bytes());
+        data[2] = error_register;
+        data[3..8].copy_from_slice(&manufacturer_specific);
+        EmcyMessage::new(self.cob_id, data)
+    }
+}
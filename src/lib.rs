@@ -4,12 +4,15 @@ use embedded_can::StandardId;
 
 pub use objectdictionary::ObjectDictionary;
 
+pub mod emcy;
 pub mod lss;
 pub mod meta;
 pub mod nmt;
+pub mod node;
 pub mod objectdictionary;
 pub mod pdo;
 pub mod sdo;
+pub mod storage;
 
 pub struct Message<const N: usize> {
     pub can_id: StandardId,
@@ -28,6 +31,8 @@ impl<const N: usize> Message<N> {
 
 pub type SdoMessage = Message<8>;
 pub type LssMessage = Message<8>;
+pub type NmtMessage = Message<2>;
+pub type EmcyMessage = Message<8>;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 #[repr(transparent)]
@@ -62,6 +67,17 @@ impl NodeId {
         unsafe { StandardId::new_unchecked(0x600 + self.0 as u16) }
     }
 
+    pub const fn emcy_cobid(self) -> StandardId {
+        // SAFETY: Maximum StandardId is 0x7FF, maximum node_id is 0x7F
+        unsafe { StandardId::new_unchecked(0x80 + self.0 as u16) }
+    }
+
+    /// COB-ID of boot-up and heartbeat messages
+    pub const fn heartbeat_cobid(self) -> StandardId {
+        // SAFETY: Maximum StandardId is 0x7FF, maximum node_id is 0x7F
+        unsafe { StandardId::new_unchecked(0x700 + self.0 as u16) }
+    }
+
     pub const NODE_ID_0: NodeId = NodeId(0);
     pub const NODE_ID_1: NodeId = NodeId(1);
     pub const NODE_ID_2: NodeId = NodeId(2);
@@ -0,0 +1,489 @@
+use core::cell::Cell;
+use core::time::Duration;
+
+use embedded_can::{Frame, Id};
+
+use crate::{LssMessage, NodeId};
+
+use super::*;
+
+/// LSS master
+///
+/// Unconfirmed services return the request to send. Confirmed services return a
+/// [`Request`] that has to be polled until it is done, feeding all received frames
+/// into [`LssMaster::on_message`] in between.
+pub struct LssMaster {
+    /// Time to wait for a response
+    pub timeout: Duration,
+    response: Cell<Option<[u8; 8]>>,
+}
+
+impl LssMaster {
+    pub fn new(timeout: Duration) -> Self {
+        LssMaster {
+            timeout,
+            response: Cell::new(None),
+        }
+    }
+
+    pub fn on_message<F: Frame>(&self, frame: &F) {
+        if frame.id() == Id::Standard(Lss::LSS_RESPONSE_ID) {
+            if let Ok(data) = frame.data().try_into() {
+                self.response.set(Some(data));
+            }
+        }
+    }
+
+    /// Switch all slaves to the "LSS configuration" state
+    pub fn switch_global_configuration(&self) -> LssMessage {
+        self.message([SWITCH_GLOBAL, 0x01, 0, 0, 0, 0, 0, 0])
+    }
+
+    /// Switch all slaves to the "LSS waiting" state
+    pub fn switch_global_waiting(&self) -> LssMessage {
+        self.message([SWITCH_GLOBAL, 0x00, 0, 0, 0, 0, 0, 0])
+    }
+
+    /// Switch the slave with the given LSS address to the "LSS configuration" state
+    pub fn switch_selective(&self, identity: &Identity) -> Request<'_, ()> {
+        let address = identity.clone().into_array().array;
+        let mut request = Request::new(self, |_| Ok(()));
+        for (command_specifier, address_data) in [
+            SWITCH_SELECTIVE_VENDOR_ID,
+            SWITCH_SELECTIVE_PRODUCT_CODE,
+            SWITCH_SELECTIVE_REVISION_NUMBER,
+        ]
+        .into_iter()
+        .zip(address)
+        {
+            request.push(address_request(command_specifier, address_data), None);
+        }
+        request.push(
+            address_request(SWITCH_SELECTIVE_SERIAL_NUMBER, address[3]),
+            Some(SWITCH_SELECTIVE_SERIAL_RESPONSE),
+        );
+        request
+    }
+
+    pub fn configure_node_id(&self, node_id: NodeId) -> Request<'_, ()> {
+        let mut request = Request::new(self, check_status);
+        request.push(
+            [CONFIGURE_NODE_ID, node_id.raw(), 0, 0, 0, 0, 0, 0],
+            Some(CONFIGURE_NODE_ID),
+        );
+        request
+    }
+
+    pub fn configure_bit_timing(&self, table_selector: u8, table_index: u8) -> Request<'_, ()> {
+        let mut request = Request::new(self, check_status);
+        request.push(
+            [
+                CONFIGURE_BIT_TIMING,
+                table_selector,
+                table_index,
+                0,
+                0,
+                0,
+                0,
+                0,
+            ],
+            Some(CONFIGURE_BIT_TIMING),
+        );
+        request
+    }
+
+    /// Let all slaves in the "LSS configuration" state switch to the configured bit timing
+    /// after `switch_delay` ms
+    pub fn activate_bit_timing(&self, switch_delay: u16) -> LssMessage {
+        let [low, high] = switch_delay.to_le_bytes();
+        self.message([ACTIVATE_BIT_TIMING, low, high, 0, 0, 0, 0, 0])
+    }
+
+    pub fn store_configuration(&self) -> Request<'_, ()> {
+        let mut request = Request::new(self, check_status);
+        request.push(
+            [STORE_CONFIGURATION, 0, 0, 0, 0, 0, 0, 0],
+            Some(STORE_CONFIGURATION),
+        );
+        request
+    }
+
+    /// Read the LSS address of the slave in the "LSS configuration" state
+    pub fn inquire_identity(&self) -> Request<'_, Identity> {
+        let mut request = Request::new(self, |responses| {
+            let address_data = |response: &[u8; 8]| {
+                u32::from_le_bytes(response[1..5].try_into().unwrap()) // Infallible
+            };
+            Ok(Identity {
+                vendor_id: address_data(&responses[0]),
+                product_code: address_data(&responses[1]),
+                revision_number: address_data(&responses[2]),
+                serial_number: address_data(&responses[3]),
+            })
+        });
+        for command_specifier in [
+            INQUIRE_VENDOR_ID,
+            INQUIRE_PRODUCT_CODE,
+            INQUIRE_REVISION_NUMBER,
+            INQUIRE_SERIAL_NUMBER,
+        ] {
+            request.push(
+                [command_specifier, 0, 0, 0, 0, 0, 0, 0],
+                Some(command_specifier),
+            );
+        }
+        request
+    }
+
+    /// Read the node-ID of the slave in the "LSS configuration" state
+    ///
+    /// `None` indicates a slave without a configured node-ID.
+    pub fn inquire_node_id(&self) -> Request<'_, Option<NodeId>> {
+        let mut request = Request::new(self, |responses| Ok(NodeId::new(responses[0][1])));
+        request.push(
+            [INQUIRE_NODE_ID, 0, 0, 0, 0, 0, 0, 0],
+            Some(INQUIRE_NODE_ID),
+        );
+        request
+    }
+
+    /// Check whether any slave without a configured node-ID is connected
+    pub fn identify_non_configured(&self) -> Request<'_, bool> {
+        let mut request = Request::new(self, |responses| Ok(!responses.is_empty()));
+        request.push(
+            [IDENTIFY_NON_CONFIGURED, 0, 0, 0, 0, 0, 0, 0],
+            Some(IDENTIFY_NON_CONFIGURED_RESPONSE),
+        );
+        request.response_optional = true;
+        request
+    }
+
+    /// Search all slaves taking part in the Fast Scan and assign them increasing node-IDs
+    /// starting with `first_node_id`
+    ///
+    /// If `store_configuration` is set the slaves are asked to store their new node-ID.
+    pub fn fast_scan(&self, first_node_id: NodeId, store_configuration: bool) -> FastScan<'_> {
+        FastScan {
+            master: self,
+            next_node_id: first_node_id.raw(),
+            store_configuration,
+            state: FastScanState::Reset,
+            lss_address: [0; 4],
+            deadline: None,
+        }
+    }
+
+    fn message(&self, data: [u8; 8]) -> LssMessage {
+        LssMessage::new(Lss::LSS_REQUEST_ID, data)
+    }
+
+    /// Send `request` or wait for the response with the given command specifier
+    fn transfer(
+        &self,
+        deadline: &mut Option<Duration>,
+        now: Duration,
+        request: [u8; 8],
+        command_specifier: u8,
+    ) -> Transfer {
+        let Some(timeout) = *deadline else {
+            // responses to previous requests are discarded
+            self.response.set(None);
+            *deadline = Some(now + self.timeout);
+            return Transfer::Send(self.message(request));
+        };
+        match self.response.take() {
+            Some(response) if response[0] == command_specifier => {
+                *deadline = None;
+                Transfer::Response(response)
+            }
+            _ if now >= timeout => {
+                *deadline = None;
+                Transfer::Timeout
+            }
+            _ => Transfer::Waiting,
+        }
+    }
+}
+
+enum Transfer {
+    Send(LssMessage),
+    Waiting,
+    Response([u8; 8]),
+    Timeout,
+}
+
+fn address_request(command_specifier: u8, address_data: u32) -> [u8; 8] {
+    let mut request = [command_specifier, 0, 0, 0, 0, 0, 0, 0];
+    request[1..5].copy_from_slice(&address_data.to_le_bytes());
+    request
+}
+
+fn check_status(responses: &[[u8; 8]]) -> Result<(), LssError> {
+    match responses[0] {
+        [_, LSS_OK, ..] => Ok(()),
+        [_, error, specific_error, ..] => Err(LssError::Failed {
+            error,
+            specific_error,
+        }),
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Step {
+    request: [u8; 8],
+    /// Command specifier of the expected response, if any
+    response: Option<u8>,
+}
+
+/// A confirmed LSS service
+pub struct Request<'a, T> {
+    master: &'a LssMaster,
+    steps: heapless::Vec<Step, 4>,
+    step: usize,
+    deadline: Option<Duration>,
+    responses: heapless::Vec<[u8; 8], 4>,
+    /// A missing response is not an error
+    response_optional: bool,
+    finish: fn(&[[u8; 8]]) -> Result<T, LssError>,
+}
+
+impl<'a, T> Request<'a, T> {
+    fn new(master: &'a LssMaster, finish: fn(&[[u8; 8]]) -> Result<T, LssError>) -> Self {
+        Request {
+            master,
+            steps: heapless::Vec::new(),
+            step: 0,
+            deadline: None,
+            responses: heapless::Vec::new(),
+            response_optional: false,
+            finish,
+        }
+    }
+
+    fn push(&mut self, request: [u8; 8], response: Option<u8>) {
+        self.steps.push(Step { request, response }).ok(); // at most 4 steps per service
+    }
+
+    pub fn poll(&mut self, now: Duration) -> Result<LssResult<T>, LssError> {
+        loop {
+            let Some(step) = self.steps.get(self.step).copied() else {
+                return (self.finish)(&self.responses).map(LssResult::Done);
+            };
+            let Some(command_specifier) = step.response else {
+                self.step += 1;
+                return Ok(LssResult::NextRequest(self.master.message(step.request)));
+            };
+            match self
+                .master
+                .transfer(&mut self.deadline, now, step.request, command_specifier)
+            {
+                Transfer::Send(message) => return Ok(LssResult::NextRequest(message)),
+                Transfer::Waiting => return Ok(LssResult::Waiting),
+                Transfer::Timeout if self.response_optional => self.step += 1,
+                Transfer::Timeout => return Err(LssError::Timeout),
+                Transfer::Response(response) => {
+                    self.responses.push(response).ok(); // at most one response per step
+                    self.step += 1;
+                }
+            }
+        }
+    }
+}
+
+pub enum LssResult<T> {
+    NextRequest(LssMessage),
+    Waiting,
+    Done(T),
+}
+
+#[derive(Copy, Clone)]
+enum FastScanState {
+    Reset,
+    Bit { lss_sub: u8, bit_checked: u8 },
+    Confirm { lss_sub: u8 },
+    ConfigureNodeId,
+    StoreConfiguration,
+    SwitchWaiting,
+    Assigned,
+    Finished,
+}
+
+/// LSS Fast Scan
+///
+/// Every found slave is switched to the "LSS configuration" state, gets the next
+/// node-ID assigned and is switched back to the "LSS waiting" state. Afterwards
+/// the scan restarts until no slave responds anymore. Found slaves don't take part
+/// again until they are switched to the "LSS configuration" state globally.
+pub struct FastScan<'a> {
+    master: &'a LssMaster,
+    next_node_id: u8,
+    store_configuration: bool,
+    state: FastScanState,
+    lss_address: [u32; 4],
+    deadline: Option<Duration>,
+}
+
+impl FastScan<'_> {
+    pub fn poll(&mut self, now: Duration) -> Result<FastScanResult, LssError> {
+        loop {
+            let (request, command_specifier) = match self.state {
+                FastScanState::Reset => (fast_scan_request(0, 128, 0, 0), IDENTIFY_RESPONSE),
+                FastScanState::Bit {
+                    lss_sub,
+                    bit_checked,
+                } => (
+                    fast_scan_request(
+                        self.lss_address[lss_sub as usize],
+                        bit_checked,
+                        lss_sub,
+                        lss_sub,
+                    ),
+                    IDENTIFY_RESPONSE,
+                ),
+                FastScanState::Confirm { lss_sub } => (
+                    fast_scan_request(
+                        self.lss_address[lss_sub as usize],
+                        0,
+                        lss_sub,
+                        (lss_sub + 1) % 4,
+                    ),
+                    IDENTIFY_RESPONSE,
+                ),
+                FastScanState::ConfigureNodeId => {
+                    if NodeId::new(self.next_node_id).is_none() {
+                        self.state = FastScanState::Finished;
+                        return Err(LssError::NoNodeIdAvailable);
+                    }
+                    (
+                        [CONFIGURE_NODE_ID, self.next_node_id, 0, 0, 0, 0, 0, 0],
+                        CONFIGURE_NODE_ID,
+                    )
+                }
+                FastScanState::StoreConfiguration => (
+                    [STORE_CONFIGURATION, 0, 0, 0, 0, 0, 0, 0],
+                    STORE_CONFIGURATION,
+                ),
+                FastScanState::SwitchWaiting => {
+                    self.state = FastScanState::Assigned;
+                    return Ok(FastScanResult::NextRequest(
+                        self.master.switch_global_waiting(),
+                    ));
+                }
+                FastScanState::Assigned => {
+                    let [vendor_id, product_code, revision_number, serial_number] =
+                        self.lss_address;
+                    let node_id = NodeId::new(self.next_node_id).unwrap(); // checked before configuring
+                    self.next_node_id += 1;
+                    self.state = FastScanState::Reset;
+                    return Ok(FastScanResult::Assigned {
+                        identity: Identity {
+                            vendor_id,
+                            product_code,
+                            revision_number,
+                            serial_number,
+                        },
+                        node_id,
+                    });
+                }
+                FastScanState::Finished => return Ok(FastScanResult::Finished),
+            };
+
+            let response =
+                match self
+                    .master
+                    .transfer(&mut self.deadline, now, request, command_specifier)
+                {
+                    Transfer::Send(message) => return Ok(FastScanResult::NextRequest(message)),
+                    Transfer::Waiting => return Ok(FastScanResult::Waiting),
+                    Transfer::Response(response) => Some(response),
+                    Transfer::Timeout => None,
+                };
+            self.on_response(response)?;
+        }
+    }
+
+    fn on_response(&mut self, response: Option<[u8; 8]>) -> Result<(), LssError> {
+        self.state = match (self.state, response) {
+            // no slave takes part in the scan
+            (FastScanState::Reset, None) => FastScanState::Finished,
+            (FastScanState::Reset, Some(_)) => {
+                self.lss_address = [0; 4];
+                FastScanState::Bit {
+                    lss_sub: 0,
+                    bit_checked: 31,
+                }
+            }
+            (
+                FastScanState::Bit {
+                    lss_sub,
+                    bit_checked,
+                },
+                response,
+            ) => {
+                // no response means no slave has this bit cleared
+                if response.is_none() {
+                    self.lss_address[lss_sub as usize] |= 1 << bit_checked;
+                }
+                match bit_checked {
+                    0 => FastScanState::Confirm { lss_sub },
+                    _ => FastScanState::Bit {
+                        lss_sub,
+                        bit_checked: bit_checked - 1,
+                    },
+                }
+            }
+            (FastScanState::Confirm { lss_sub: 3 }, Some(_)) => FastScanState::ConfigureNodeId,
+            (FastScanState::Confirm { lss_sub }, Some(_)) => FastScanState::Bit {
+                lss_sub: lss_sub + 1,
+                bit_checked: 31,
+            },
+            (FastScanState::ConfigureNodeId, Some(response)) => {
+                check_status(&[response])?;
+                if self.store_configuration {
+                    FastScanState::StoreConfiguration
+                } else {
+                    FastScanState::SwitchWaiting
+                }
+            }
+            (FastScanState::StoreConfiguration, Some(response)) => {
+                check_status(&[response])?;
+                FastScanState::SwitchWaiting
+            }
+            _ => {
+                self.state = FastScanState::Finished;
+                return Err(LssError::Timeout);
+            }
+        };
+        Ok(())
+    }
+}
+
+fn fast_scan_request(id_number: u32, bit_checked: u8, lss_sub: u8, lss_next: u8) -> [u8; 8] {
+    let mut request = address_request(FAST_SCAN, id_number);
+    request[5] = bit_checked;
+    request[6] = lss_sub;
+    request[7] = lss_next;
+    request
+}
+
+pub enum FastScanResult {
+    NextRequest(LssMessage),
+    Waiting,
+    /// A slave was found and configured, the scan continues
+    Assigned {
+        identity: Identity,
+        node_id: NodeId,
+    },
+    /// No slave responds anymore
+    Finished,
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub enum LssError {
+    /// The slave did not respond in time
+    Timeout,
+    /// The slave responded with an error code
+    Failed { error: u8, specific_error: u8 },
+    /// The Fast Scan ran out of node-IDs
+    NoNodeIdAvailable,
+}
@@ -3,6 +3,10 @@ use embedded_can::{Id, StandardId};
 use crate::objectdictionary::OdArray;
 use crate::{LssMessage, NodeId};
 
+pub use master::LssMaster;
+
+pub mod master;
+
 type RequestResult = Option<[u8; 8]>;
 
 const SWITCH_GLOBAL: u8 = 0x04;
@@ -24,7 +28,10 @@ const IDENTIFY_REVISION_NUMBER_HIGH: u8 = 0x49;
 const IDENTIFY_SERIAL_NUMBER_LOW: u8 = 0x4A;
 const IDENTIFY_SERIAL_NUMBER_HIGH: u8 = 0x4B;
 
+const IDENTIFY_NON_CONFIGURED: u8 = 0x4C;
+
 const IDENTIFY_RESPONSE: u8 = 0x4F;
+const IDENTIFY_NON_CONFIGURED_RESPONSE: u8 = 0x50;
 
 const FAST_SCAN: u8 = 0x51;
 
@@ -38,9 +45,34 @@ const INQUIRE_NODE_ID: u8 = 0x5E;
 const LSS_OK: u8 = 0x00;
 const LSS_GENERIC_ERROR: u8 = 0x01;
 const LSS_STORE_FAILED: u8 = 0x02;
+const LSS_IMPLEMENTATION_SPECIFIC_ERROR: u8 = 0xFF;
 
 pub static STANDARD_BAUDRATE_TABLE: &[u16] = &[1000, 800, 500, 250, 125, 100, 50, 20, 10];
 
+/// Bit timing requested by the "Configure bit timing parameters" service
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BitTiming {
+    /// Bit rate in kbit/s from [`STANDARD_BAUDRATE_TABLE`] (table selector 0)
+    Standard(u16),
+    /// Entry of a manufacturer-specific table (table selector 128 to 255)
+    User { table_selector: u8, table_index: u8 },
+}
+
+impl BitTiming {
+    pub fn new(table_selector: u8, table_index: u8) -> Option<Self> {
+        match table_selector {
+            0 => STANDARD_BAUDRATE_TABLE
+                .get(table_index as usize)
+                .map(|&baudrate| BitTiming::Standard(baudrate)),
+            128..=255 => Some(BitTiming::User {
+                table_selector,
+                table_index,
+            }),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct Identity {
     pub vendor_id: u32,
@@ -60,13 +92,30 @@ impl Identity {
     }
 }
 
+/// Slaves taking part in the LSS Fast Scan
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum FastScanParticipation {
+    /// Only slaves without a configured node-ID (default)
+    Unconfigured,
+    Always,
+    Never,
+}
+
 pub struct Lss {
+    /// Configured by LSS, becomes active with the next "reset communication"
     pub(crate) node_id: Option<NodeId>,
+    /// In use by the device, `None` if it is not configured
+    pub(crate) active_node_id: Option<NodeId>,
+    pub fast_scan_participation: FastScanParticipation,
     lss_address: [u32; 4],
     mode: LssMode,
     partial_command_state: PartialCommandState,
-    expected_lss_sub: u8, // used in fast_scan
+    expected_lss_sub: Option<u8>, // used in fast_scan, `None` if not taking part
+    /// Identified by a Fast Scan since the last "switch state global" to configuration,
+    /// so restarted scans find the other slaves
+    found_by_fast_scan: bool,
     node_id_changed: bool,
+    bit_timing: Option<BitTiming>,
 }
 
 impl Lss {
@@ -80,14 +129,27 @@ impl Lss {
     pub fn new(node_id: Option<NodeId>, identity: Identity) -> Self {
         Lss {
             node_id,
+            active_node_id: node_id,
+            fast_scan_participation: FastScanParticipation::Unconfigured,
             lss_address: identity.into_array().array,
             mode: LssMode::Wait,
             partial_command_state: PartialCommandState::Init,
-            expected_lss_sub: 0,
+            expected_lss_sub: None,
+            found_by_fast_scan: false,
             node_id_changed: false,
+            bit_timing: None,
         }
     }
 
+    /// Set the node-ID the device uses, e.g. after applying the one configured by LSS.
+    ///
+    /// Only devices without an active node-ID count as non-configured (CiA 305),
+    /// a configured node-ID that is not applied yet doesn't change that.
+    /// [`Node`](crate::node::Node) calls this on "reset communication".
+    pub fn set_active_node_id(&mut self, node_id: Option<NodeId>) {
+        self.active_node_id = node_id;
+    }
+
     pub fn on_message<F: embedded_can::Frame>(
         &mut self,
         frame: &F,
@@ -110,14 +172,32 @@ impl Lss {
     ) -> Option<LssMessage> {
         let command_specifier = request[0];
 
+        // After configuring the bit timing only the switch mode and bit timing services are allowed
+        if self.bit_timing.is_some()
+            && !matches!(
+                command_specifier,
+                SWITCH_GLOBAL
+                    | SWITCH_SELECTIVE_VENDOR_ID
+                    | SWITCH_SELECTIVE_PRODUCT_CODE
+                    | SWITCH_SELECTIVE_REVISION_NUMBER
+                    | SWITCH_SELECTIVE_SERIAL_NUMBER
+                    | CONFIGURE_BIT_TIMING
+                    | ACTIVATE_BIT_TIMING
+            )
+        {
+            return None;
+        }
+
         // check services that don't care about mode
         match command_specifier {
             SWITCH_GLOBAL => {
                 self.partial_command_state = PartialCommandState::Init;
+                self.expected_lss_sub = None;
                 // Switch state global service
                 match request[1] {
                     0x00 => {
                         self.mode = LssMode::Wait;
+                        self.bit_timing = None;
                         if let (Some(node_id), true) = (self.node_id, self.node_id_changed) {
                             callback.on_new_node_id(node_id);
                         }
@@ -126,6 +206,7 @@ impl Lss {
                     }
                     0x01 => {
                         self.mode = LssMode::Configuration;
+                        self.found_by_fast_scan = false;
                     }
                     _ => {}
                 }
@@ -152,6 +233,15 @@ impl Lss {
                     .identify(request)
                     .map(|response| LssMessage::new(Self::LSS_RESPONSE_ID, response));
             }
+            IDENTIFY_NON_CONFIGURED => {
+                // LSS identify non-configured remote slave service
+                return self.active_node_id.is_none().then(|| {
+                    LssMessage::new(
+                        Self::LSS_RESPONSE_ID,
+                        [IDENTIFY_NON_CONFIGURED_RESPONSE, 0, 0, 0, 0, 0, 0, 0],
+                    )
+                });
+            }
             FAST_SCAN => {
                 return self
                     .fast_scan(request)
@@ -174,11 +264,11 @@ impl Lss {
             }
             CONFIGURE_BIT_TIMING => {
                 // Configure bit timing parameters service
-                self.set_bit_timing(request)
+                self.set_bit_timing(request, callback)
             }
             ACTIVATE_BIT_TIMING => {
                 // Activate bit timing parameters service
-                todo!()
+                self.activate_bit_timing(request, callback)
             }
             STORE_CONFIGURATION => {
                 // Store configuration service
@@ -201,6 +291,17 @@ impl Lss {
         result.map(|response| LssMessage::new(Self::LSS_RESPONSE_ID, response))
     }
 
+    fn takes_part_in_fast_scan(&self) -> bool {
+        if self.found_by_fast_scan {
+            return false;
+        }
+        match self.fast_scan_participation {
+            FastScanParticipation::Unconfigured => self.active_node_id.is_none(),
+            FastScanParticipation::Always => true,
+            FastScanParticipation::Never => false,
+        }
+    }
+
     fn lss_node_id(&self) -> u8 {
         // CiA 305: a node-ID of 0xFF identifies a not configured CANopen device
         self.node_id.map_or(0xFF, NodeId::raw)
@@ -229,16 +330,44 @@ impl Lss {
         Some([STORE_CONFIGURATION, status, 0, 0, 0, 0, 0, 0])
     }
 
-    fn set_bit_timing(&mut self, request: &[u8; 8]) -> RequestResult {
-        // TODO:
-        //  After execution of the Configure Bit
-        //  Timing Parameters service the node may not execute any remote LSS services except the services Configure Bit
-        //  Timing Parameters, Activate Bit Timing Parameters and Switch Mode.
-        let _command_specifier = request[0];
-        let _table_selector = request[1];
-        let _table_index = request[2];
+    fn set_bit_timing(
+        &mut self,
+        request: &[u8; 8],
+        callback: &mut impl LssCallback,
+    ) -> RequestResult {
+        let table_selector = request[1];
+        let table_index = request[2];
+
+        let result = match BitTiming::new(table_selector, table_index) {
+            Some(bit_timing) => callback
+                .configure_bit_timing(bit_timing)
+                .map(|()| bit_timing),
+            None => Err(ConfigureBitTimingError::NotSupported),
+        };
+        let (status, specific_error) = match result {
+            Ok(bit_timing) => {
+                self.bit_timing = Some(bit_timing);
+                (LSS_OK, 0)
+            }
+            Err(ConfigureBitTimingError::NotSupported) => (LSS_GENERIC_ERROR, 0),
+            Err(ConfigureBitTimingError::Specific(error)) => {
+                (LSS_IMPLEMENTATION_SPECIFIC_ERROR, error)
+            }
+        };
+
+        Some([CONFIGURE_BIT_TIMING, status, specific_error, 0, 0, 0, 0, 0])
+    }
 
-        todo!() // Some([command_specifier, LSS_OK, 0, 0, 0, 0, 0, 0])
+    fn activate_bit_timing(
+        &mut self,
+        request: &[u8; 8],
+        callback: &mut impl LssCallback,
+    ) -> RequestResult {
+        let switch_delay = u16::from_le_bytes([request[1], request[2]]);
+        if let Some(bit_timing) = self.bit_timing.take() {
+            callback.activate_bit_timing(bit_timing, switch_delay);
+        }
+        None
     }
 
     fn inquire(&self, command_specifier: u8) -> RequestResult {
@@ -255,34 +384,29 @@ impl Lss {
         let address_data = u32::from_le_bytes(address_data);
 
         match (command_specifier, self.partial_command_state) {
-            (SWITCH_SELECTIVE_VENDOR_ID, _) => {
-                if address_data == self.lss_address[0] {
-                    self.partial_command_state = PartialCommandState::SwitchVendorIdMatched;
-                    return None;
-                }
+            (SWITCH_SELECTIVE_VENDOR_ID, _) if address_data == self.lss_address[0] => {
+                self.partial_command_state = PartialCommandState::SwitchVendorIdMatched;
+                return None;
             }
-            (SWITCH_SELECTIVE_PRODUCT_CODE, PartialCommandState::SwitchVendorIdMatched) => {
-                if address_data == self.lss_address[1] {
-                    self.partial_command_state = PartialCommandState::SwitchProductCodeMatched;
-                    return None;
-                }
+            (SWITCH_SELECTIVE_PRODUCT_CODE, PartialCommandState::SwitchVendorIdMatched)
+                if address_data == self.lss_address[1] =>
+            {
+                self.partial_command_state = PartialCommandState::SwitchProductCodeMatched;
+                return None;
             }
-            (SWITCH_SELECTIVE_REVISION_NUMBER, PartialCommandState::SwitchProductCodeMatched) => {
-                if address_data == self.lss_address[2] {
-                    self.partial_command_state =
-                        PartialCommandState::SwitchRevisionNumberCodeMatched;
-                    return None;
-                }
+            (SWITCH_SELECTIVE_REVISION_NUMBER, PartialCommandState::SwitchProductCodeMatched)
+                if address_data == self.lss_address[2] =>
+            {
+                self.partial_command_state = PartialCommandState::SwitchRevisionNumberCodeMatched;
+                return None;
             }
             (
                 SWITCH_SELECTIVE_SERIAL_NUMBER,
                 PartialCommandState::SwitchRevisionNumberCodeMatched,
-            ) => {
-                if address_data == self.lss_address[3] {
-                    self.partial_command_state = PartialCommandState::Init;
-                    self.mode = LssMode::Configuration;
-                    return Some([SWITCH_SELECTIVE_SERIAL_RESPONSE, 0, 0, 0, 0, 0, 0, 0]);
-                }
+            ) if address_data == self.lss_address[3] => {
+                self.partial_command_state = PartialCommandState::Init;
+                self.mode = LssMode::Configuration;
+                return Some([SWITCH_SELECTIVE_SERIAL_RESPONSE, 0, 0, 0, 0, 0, 0, 0]);
             }
             _ => {}
         }
@@ -297,53 +421,45 @@ impl Lss {
         let address_data = u32::from_le_bytes(address_data);
 
         match (command_specifier, self.partial_command_state) {
-            (IDENTIFY_VENDOR_ID, _) => {
-                if address_data == self.lss_address[0] {
-                    self.partial_command_state = PartialCommandState::IdentifyVendorIdMatched;
-                    return None;
-                }
+            (IDENTIFY_VENDOR_ID, _) if address_data == self.lss_address[0] => {
+                self.partial_command_state = PartialCommandState::IdentifyVendorIdMatched;
+                return None;
             }
-            (IDENTIFY_PRODUCT_CODE, PartialCommandState::IdentifyVendorIdMatched) => {
-                if address_data == self.lss_address[1] {
-                    self.partial_command_state = PartialCommandState::IdentifyProductCodeMatched;
-                    return None;
-                }
+            (IDENTIFY_PRODUCT_CODE, PartialCommandState::IdentifyVendorIdMatched)
+                if address_data == self.lss_address[1] =>
+            {
+                self.partial_command_state = PartialCommandState::IdentifyProductCodeMatched;
+                return None;
             }
-            (IDENTIFY_REVISION_NUMBER_LOW, PartialCommandState::IdentifyProductCodeMatched) => {
-                if address_data <= self.lss_address[2] {
-                    self.partial_command_state =
-                        PartialCommandState::IdentifyRevisionNumberLowCodeMatched;
-                    return None;
-                }
+            (IDENTIFY_REVISION_NUMBER_LOW, PartialCommandState::IdentifyProductCodeMatched)
+                if address_data <= self.lss_address[2] =>
+            {
+                self.partial_command_state =
+                    PartialCommandState::IdentifyRevisionNumberLowCodeMatched;
+                return None;
             }
             (
                 IDENTIFY_REVISION_NUMBER_HIGH,
                 PartialCommandState::IdentifyRevisionNumberLowCodeMatched,
-            ) => {
-                if address_data >= self.lss_address[2] {
-                    self.partial_command_state =
-                        PartialCommandState::IdentifyRevisionNumberHighCodeMatched;
-                    return None;
-                }
+            ) if address_data >= self.lss_address[2] => {
+                self.partial_command_state =
+                    PartialCommandState::IdentifyRevisionNumberHighCodeMatched;
+                return None;
             }
             (
                 IDENTIFY_SERIAL_NUMBER_LOW,
                 PartialCommandState::IdentifyRevisionNumberHighCodeMatched,
-            ) => {
-                if address_data <= self.lss_address[3] {
-                    self.partial_command_state =
-                        PartialCommandState::IdentifySerialNumberLowCodeMatched;
-                    return None;
-                }
+            ) if address_data <= self.lss_address[3] => {
+                self.partial_command_state =
+                    PartialCommandState::IdentifySerialNumberLowCodeMatched;
+                return None;
             }
             (
                 IDENTIFY_SERIAL_NUMBER_HIGH,
                 PartialCommandState::IdentifySerialNumberLowCodeMatched,
-            ) => {
-                if address_data >= self.lss_address[3] {
-                    self.partial_command_state = PartialCommandState::Init;
-                    return Some([IDENTIFY_RESPONSE, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
-                }
+            ) if address_data >= self.lss_address[3] => {
+                self.partial_command_state = PartialCommandState::Init;
+                return Some([IDENTIFY_RESPONSE, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
             }
             _ => {}
         }
@@ -363,18 +479,27 @@ impl Lss {
         let lss_next = request[7];
 
         if bit_checked == 128 {
-            // TODO check if we want to participate
-            self.expected_lss_sub = 0;
+            // A new scan starts, forget about the previous one
+            self.expected_lss_sub = None;
+            if !self.takes_part_in_fast_scan() {
+                return None;
+            }
+            self.expected_lss_sub = Some(0);
             return Some([IDENTIFY_RESPONSE, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
-        } else if lss_sub == self.expected_lss_sub && lss_sub < 4 && bit_checked < 32 {
+        }
+
+        let expected_lss_sub = self.expected_lss_sub?;
+        if lss_sub == expected_lss_sub && lss_sub < 4 && lss_next < 4 && bit_checked < 32 {
             let bit_mask = u32::MAX << bit_checked;
 
             if (self.lss_address[lss_sub as usize] ^ id_number) & bit_mask == 0 {
                 // Checked bits match
-                self.expected_lss_sub = lss_next; // only update lss_next if we're still matching
+                self.expected_lss_sub = Some(lss_next); // only update lss_next if we're still matching
                 if bit_checked == 0 && lss_next < lss_sub {
                     // Complete match, scan completed
                     self.mode = LssMode::Configuration;
+                    self.expected_lss_sub = None;
+                    self.found_by_fast_scan = true;
                 }
                 return Some([IDENTIFY_RESPONSE, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
             }
@@ -391,6 +516,25 @@ pub trait LssCallback {
     ///
     /// This might be the time to reset the node
     fn on_new_node_id(&mut self, node_id: NodeId);
+    /// We're in the "LSS configuration" state and the "Configure bit timing parameters"
+    /// command was sent
+    ///
+    /// Check whether the bit timing is supported. It must not be applied before
+    /// [`LssCallback::activate_bit_timing`] is called.
+    fn configure_bit_timing(
+        &mut self,
+        bit_timing: BitTiming,
+    ) -> Result<(), ConfigureBitTimingError> {
+        let _ = bit_timing;
+        Err(ConfigureBitTimingError::NotSupported)
+    }
+    /// The "Activate bit timing parameters" command was sent
+    ///
+    /// The application has to stop transmitting, wait `switch_delay` ms, switch
+    /// to `bit_timing` and wait another `switch_delay` ms before transmitting again.
+    fn activate_bit_timing(&mut self, bit_timing: BitTiming, switch_delay: u16) {
+        let _ = (bit_timing, switch_delay);
+    }
 }
 
 pub enum StoreConfigurationError {
@@ -398,6 +542,12 @@ pub enum StoreConfigurationError {
     Failed,
 }
 
+pub enum ConfigureBitTimingError {
+    NotSupported,
+    /// Implementation specific error code
+    Specific(u8),
+}
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 enum LssMode {
     Configuration,
@@ -547,4 +697,121 @@ mod test {
         };
         assert!(test_identify_request(&mut lss, revision_too_high).is_none());
     }
+
+    #[test]
+    fn test_identify_non_configured() {
+        let identity = Identity {
+            vendor_id: 1,
+            product_code: 2,
+            revision_number: 3,
+            serial_number: 4,
+        };
+        let request = [IDENTIFY_NON_CONFIGURED, 0, 0, 0, 0, 0, 0, 0];
+
+        let mut lss = Lss::new(Some(NodeId::NODE_ID_1), identity.clone());
+        assert!(lss.on_request(&request, &mut NoopLssCallback).is_none());
+
+        let mut lss = Lss::new(None, identity);
+        let response = lss.on_request(&request, &mut NoopLssCallback).unwrap();
+        assert_eq!(response.data[0], IDENTIFY_NON_CONFIGURED_RESPONSE);
+
+        // still answered in configuration mode
+        lss.on_request(&[SWITCH_GLOBAL, 1, 0, 0, 0, 0, 0, 0], &mut NoopLssCallback);
+        assert!(lss.on_request(&request, &mut NoopLssCallback).is_some());
+
+        // and after a node-ID was configured, until it is applied
+        lss.on_request(
+            &[CONFIGURE_NODE_ID, 7, 0, 0, 0, 0, 0, 0],
+            &mut NoopLssCallback,
+        );
+        assert!(lss.on_request(&request, &mut NoopLssCallback).is_some());
+        lss.set_active_node_id(NodeId::new(7));
+        assert!(lss.on_request(&request, &mut NoopLssCallback).is_none());
+    }
+
+    #[derive(Default)]
+    struct BitTimingCallback {
+        activated: Option<(BitTiming, u16)>,
+    }
+
+    impl LssCallback for BitTimingCallback {
+        fn store_configuration(&mut self, _: NodeId) -> Result<(), StoreConfigurationError> {
+            Ok(())
+        }
+
+        fn on_new_node_id(&mut self, _: NodeId) {}
+
+        fn configure_bit_timing(
+            &mut self,
+            bit_timing: BitTiming,
+        ) -> Result<(), ConfigureBitTimingError> {
+            match bit_timing {
+                BitTiming::Standard(125 | 250) => Ok(()),
+                BitTiming::Standard(_) => Err(ConfigureBitTimingError::NotSupported),
+                BitTiming::User { .. } => Err(ConfigureBitTimingError::Specific(0x42)),
+            }
+        }
+
+        fn activate_bit_timing(&mut self, bit_timing: BitTiming, switch_delay: u16) {
+            self.activated = Some((bit_timing, switch_delay));
+        }
+    }
+
+    #[test]
+    fn test_bit_timing() {
+        let mut lss = Lss::new(
+            Some(NodeId::NODE_ID_1),
+            Identity {
+                vendor_id: 1,
+                product_code: 2,
+                revision_number: 3,
+                serial_number: 4,
+            },
+        );
+        let mut callback = BitTimingCallback::default();
+        let mut request = |lss: &mut Lss, request: [u8; 8]| {
+            lss.on_request(&request, &mut callback)
+                .map(|response| response.data)
+        };
+
+        // requires configuration mode
+        assert_eq!(
+            request(&mut lss, [CONFIGURE_BIT_TIMING, 0, 4, 0, 0, 0, 0, 0]),
+            None
+        );
+        request(&mut lss, [SWITCH_GLOBAL, 1, 0, 0, 0, 0, 0, 0]);
+
+        // 500 kbit/s is not supported
+        assert_eq!(
+            request(&mut lss, [CONFIGURE_BIT_TIMING, 0, 2, 0, 0, 0, 0, 0]),
+            Some([CONFIGURE_BIT_TIMING, LSS_GENERIC_ERROR, 0, 0, 0, 0, 0, 0])
+        );
+        // reserved table selector
+        assert_eq!(
+            request(&mut lss, [CONFIGURE_BIT_TIMING, 1, 0, 0, 0, 0, 0, 0]),
+            Some([CONFIGURE_BIT_TIMING, LSS_GENERIC_ERROR, 0, 0, 0, 0, 0, 0])
+        );
+        // user table
+        assert_eq!(
+            request(&mut lss, [CONFIGURE_BIT_TIMING, 128, 0, 0, 0, 0, 0, 0]),
+            Some([CONFIGURE_BIT_TIMING, 0xFF, 0x42, 0, 0, 0, 0, 0])
+        );
+        // 125 kbit/s
+        assert_eq!(
+            request(&mut lss, [CONFIGURE_BIT_TIMING, 0, 4, 0, 0, 0, 0, 0]),
+            Some([CONFIGURE_BIT_TIMING, LSS_OK, 0, 0, 0, 0, 0, 0])
+        );
+
+        // other services are not allowed anymore
+        assert_eq!(
+            request(&mut lss, [INQUIRE_NODE_ID, 0, 0, 0, 0, 0, 0, 0]),
+            None
+        );
+
+        assert_eq!(
+            request(&mut lss, [ACTIVATE_BIT_TIMING, 0xE8, 0x03, 0, 0, 0, 0, 0]),
+            None
+        );
+        assert_eq!(callback.activated, Some((BitTiming::Standard(125), 1000)));
+    }
 }
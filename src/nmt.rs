@@ -1,6 +1,7 @@
 use embedded_can::{Id, StandardId};
 
-use crate::NodeId;
+use crate::objectdictionary::ObjectDictionary;
+use crate::{NmtMessage, NodeId};
 
 const START_REMOTE_NODE: u8 = 0x01;
 const STOP_REMOTE_NODE: u8 = 0x02;
@@ -30,6 +31,20 @@ impl NmtRequest {
         }
     }
 
+    /// Whether the request resets the communication objects.
+    pub fn is_reset(self) -> bool {
+        self.reset().is_some()
+    }
+
+    /// The reset sub-state entered because of this request.
+    pub fn reset(self) -> Option<NmtReset> {
+        match self {
+            NmtRequest::ResetNode => Some(NmtReset::ResetApplication),
+            NmtRequest::ResetCommunication => Some(NmtReset::ResetCommunication),
+            _ => None,
+        }
+    }
+
     pub fn next_state(self) -> NmtState {
         match self {
             NmtRequest::StartRemoteNode => NmtState::Operational,
@@ -49,9 +64,89 @@ pub enum NmtState {
     Stopped = 4,
 }
 
+impl NmtState {
+    /// Parse the state as sent in boot-up and heartbeat messages.
+    ///
+    /// The toggle bit used by node guarding (bit 7) is ignored.
+    pub fn from_u8(value: u8) -> Option<NmtState> {
+        match value & 0x7F {
+            0 => Some(NmtState::Initialisation),
+            127 => Some(NmtState::PreOperational),
+            5 => Some(NmtState::Operational),
+            4 => Some(NmtState::Stopped),
+            _ => None,
+        }
+    }
+
+    /// Whether `object` may be used in this state according to CiA 301, Table 83.
+    pub const fn allows(self, object: CommunicationObject) -> bool {
+        match object {
+            CommunicationObject::BootUp => matches!(self, NmtState::Initialisation),
+            CommunicationObject::Pdo => matches!(self, NmtState::Operational),
+            CommunicationObject::Sdo
+            | CommunicationObject::Sync
+            | CommunicationObject::Time
+            | CommunicationObject::Emcy => {
+                matches!(self, NmtState::PreOperational | NmtState::Operational)
+            }
+            CommunicationObject::Nmt | CommunicationObject::ErrorControl => {
+                !matches!(self, NmtState::Initialisation)
+            }
+        }
+    }
+}
+
+/// Sub-states of [`NmtState::Initialisation`] that re-initialise the node
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum NmtReset {
+    /// Reset the manufacturer-specific and standardized device profile areas,
+    /// then continue with [`NmtReset::ResetCommunication`].
+    ResetApplication,
+    /// Reset the communication profile area (0x1000 to 0x1FFF).
+    ResetCommunication,
+}
+
+/// NMT startup (object 0x1F80) as defined in CiA 302-2
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct NmtStartup(pub u32);
+
+impl NmtStartup {
+    pub const INDEX: u16 = 0x1F80;
+
+    /// The device is the NMT master.
+    pub const NMT_MASTER: u32 = 1 << 0;
+    /// The NMT master starts all nodes with a single broadcast.
+    pub const START_ALL_NODES: u32 = 1 << 1;
+    /// The device does not enter [`NmtState::Operational`] autonomously.
+    pub const NO_AUTO_OPERATIONAL: u32 = 1 << 2;
+
+    fn from_od<T, const N: usize>(od: &mut ObjectDictionary<T, N>) -> Option<Self> {
+        od.get(Self::INDEX, 0).ok().map(NmtStartup)
+    }
+
+    pub fn enters_operational(self) -> bool {
+        self.0 & Self::NO_AUTO_OPERATIONAL == 0
+    }
+}
+
+/// Communication objects whose use depends on the [`NmtState`]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum CommunicationObject {
+    BootUp,
+    Pdo,
+    Sdo,
+    Sync,
+    Time,
+    Emcy,
+    Nmt,
+    /// Heartbeat and node guarding
+    ErrorControl,
+}
+
 pub struct Nmt {
     pub(crate) node_id: NodeId,
     pub state: NmtState,
+    pending_reset: Option<NmtReset>,
 }
 
 impl Nmt {
@@ -61,9 +156,19 @@ impl Nmt {
         Nmt {
             node_id,
             state: NmtState::Initialisation,
+            pending_reset: None,
         }
     }
 
+    pub fn node_id(&self) -> NodeId {
+        self.node_id
+    }
+
+    /// Use another node-ID for all following messages
+    pub fn set_node_id(&mut self, node_id: NodeId) {
+        self.node_id = node_id;
+    }
+
     pub fn on_message<F: embedded_can::Frame>(
         &mut self,
         frame: &F,
@@ -87,13 +192,108 @@ impl Nmt {
     ) -> Option<F> {
         let nmt_request = NmtRequest::from_u8(command_code)?;
         self.state = callback.on_nmt_request(nmt_request);
+        if self.state == NmtState::Initialisation {
+            // a reset application also includes a reset communication
+            self.pending_reset = match (self.pending_reset, nmt_request.reset()) {
+                (Some(NmtReset::ResetApplication), _) => Some(NmtReset::ResetApplication),
+                (_, reset) => reset,
+            };
+        }
         None
     }
 
+    /// The reset requested by the NMT master that has not been executed yet.
+    pub fn pending_reset(&self) -> Option<NmtReset> {
+        self.pending_reset
+    }
+
+    /// Execute a pending reset.
+    ///
+    /// Runs the hooks of `callback` for the reset sub-states and finishes
+    /// the initialisation like [`Nmt::boot_up`].
+    pub fn reset<F: embedded_can::Frame, T, const N: usize>(
+        &mut self,
+        od: &mut ObjectDictionary<T, N>,
+        callback: &mut impl NmtResetCallback<T>,
+    ) -> Option<F> {
+        self.run_reset_hooks(od, callback)?;
+        Some(self.boot_up(od))
+    }
+
+    /// Execute a pending reset up to the boot-up.
+    ///
+    /// Runs the hooks of `callback` for the reset sub-states and returns the executed
+    /// reset, [`Nmt::boot_up`] has to be called afterwards.
+    pub fn run_reset_hooks<T, const N: usize>(
+        &mut self,
+        od: &mut ObjectDictionary<T, N>,
+        callback: &mut impl NmtResetCallback<T>,
+    ) -> Option<NmtReset> {
+        let reset = self.pending_reset.take()?;
+        if reset == NmtReset::ResetApplication {
+            callback.reset_application(&mut od.data);
+        }
+        callback.reset_communication(&mut od.data);
+        Some(reset)
+    }
+
+    /// Finish the initialisation.
+    ///
+    /// Enters [`NmtState::PreOperational`] and returns the boot-up message
+    /// which has to be sent afterwards. If the NMT startup object (0x1F80)
+    /// exists and allows it, the node then continues to [`NmtState::Operational`].
+    pub fn boot_up<F: embedded_can::Frame, T, const N: usize>(
+        &mut self,
+        od: &mut ObjectDictionary<T, N>,
+    ) -> F {
+        let boot_up_message = self.boot_up_message();
+        self.state = NmtState::PreOperational;
+        if NmtStartup::from_od(od).is_some_and(NmtStartup::enters_operational) {
+            self.state = NmtState::Operational;
+        }
+        boot_up_message
+    }
+
+    /// Whether `object` may be used in the current state.
+    ///
+    /// Received frames of a disallowed object shall be ignored
+    /// and frames of a disallowed object shall not be sent.
+    pub fn allows(&self, object: CommunicationObject) -> bool {
+        self.state.allows(object)
+    }
+
+    /// Run `service` only if `object` is allowed in the current state.
+    ///
+    /// ```
+    /// # use canopen::nmt::{CommunicationObject, Nmt};
+    /// # use canopen::sdo::SdoServer;
+    /// # use canopen::ObjectDictionary;
+    /// # fn demo<F: embedded_can::Frame, T, const N: usize>(
+    /// #     nmt: &Nmt, sdo_server: &mut SdoServer, frame: &F, od: &mut ObjectDictionary<T, N>,
+    /// # ) -> Option<F> {
+    /// nmt.gate(CommunicationObject::Sdo, || sdo_server.on_message(frame, od))
+    /// # }
+    /// ```
+    pub fn gate<R>(
+        &self,
+        object: CommunicationObject,
+        service: impl FnOnce() -> Option<R>,
+    ) -> Option<R> {
+        if self.allows(object) {
+            service()
+        } else {
+            None
+        }
+    }
+
+    pub fn heartbeat_message<F: embedded_can::Frame>(&self) -> F {
+        let data = [self.state as u8];
+        F::new(self.node_id.heartbeat_cobid(), &data).expect("data should fit")
+    }
+
     pub fn boot_up_message<F: embedded_can::Frame>(&mut self) -> F {
-        let node_id = unsafe { StandardId::new_unchecked(0x700 + self.node_id.raw() as u16) };
-        let data = [0x00];
-        F::new(node_id, &data).expect("data should fit")
+        let data = [NmtState::Initialisation as u8];
+        F::new(self.node_id.heartbeat_cobid(), &data).expect("data should fit")
     }
 }
 
@@ -103,3 +303,87 @@ pub trait NmtCallback {
         nmt_request.next_state()
     }
 }
+
+/// Application hooks for the reset sub-states of [`NmtState::Initialisation`]
+pub trait NmtResetCallback<T> {
+    /// Restore the manufacturer-specific and standardized device profile areas
+    /// (0x2000 to 0x9FFF) to their power-on values.
+    fn reset_application(&mut self, data: &mut T);
+    /// Restore the communication profile area (0x1000 to 0x1FFF) to its power-on values,
+    /// e.g. with [`TPDO::reset`](crate::pdo::TPDO::reset) and [`RPDO::reset`](crate::pdo::RPDO::reset).
+    fn reset_communication(&mut self, data: &mut T);
+}
+
+/// NMT master
+///
+/// Builds NMT command frames and keeps track of the remote node states
+/// reported through boot-up and heartbeat messages.
+pub struct NmtMaster {
+    states: [Option<NmtState>; 128],
+}
+
+impl NmtMaster {
+    pub fn new() -> NmtMaster {
+        NmtMaster {
+            states: [None; 128],
+        }
+    }
+
+    /// Create a command for a single node.
+    pub fn request(&self, nmt_request: NmtRequest, node_id: NodeId) -> NmtMessage {
+        NmtMessage::new(Nmt::NMT_REQUEST_ID, [nmt_request as u8, node_id.raw()])
+    }
+
+    /// Create a command for all nodes.
+    pub fn broadcast(&self, nmt_request: NmtRequest) -> NmtMessage {
+        NmtMessage::new(Nmt::NMT_REQUEST_ID, [nmt_request as u8, 0])
+    }
+
+    /// Update the state table from boot-up and heartbeat messages.
+    ///
+    /// Returns the node whose state was reported.
+    pub fn on_message<F: embedded_can::Frame>(&mut self, frame: &F) -> Option<(NodeId, NmtState)> {
+        let Id::Standard(id) = frame.id() else {
+            return None;
+        };
+        let node_id = match id.as_raw() {
+            0x701..=0x77F => NodeId::new((id.as_raw() - 0x700) as u8)?,
+            _ => return None,
+        };
+        match frame.data() {
+            &[state] => {
+                let state = NmtState::from_u8(state)?;
+                self.states[node_id.raw() as usize] = Some(state);
+                Some((node_id, state))
+            }
+            _ => None,
+        }
+    }
+
+    /// The last state reported by the node, if any.
+    pub fn state(&self, node_id: NodeId) -> Option<NmtState> {
+        self.states[node_id.raw() as usize]
+    }
+
+    /// Forget the state of the node, e.g. after a heartbeat timeout.
+    pub fn clear_state(&mut self, node_id: NodeId) {
+        self.states[node_id.raw() as usize] = None;
+    }
+
+    /// All nodes that have reported a state.
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, NmtState)> + '_ {
+        self.states
+            .iter()
+            .enumerate()
+            .filter_map(|(node_id, state)| {
+                // SAFETY: the state table has 128 entries
+                Some((unsafe { NodeId::new_unchecked(node_id as u8) }, (*state)?))
+            })
+    }
+}
+
+impl Default for NmtMaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
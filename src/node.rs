@@ -0,0 +1,425 @@
+use core::time::Duration;
+
+use embedded_can::{Frame, Id, StandardId};
+
+use crate::emcy::Emcy;
+use crate::lss::{Lss, LssCallback};
+use crate::nmt::{CommunicationObject, Nmt, NmtCallback, NmtReset, NmtResetCallback, NmtState};
+use crate::objectdictionary::{ODError, ObjectDictionary, OdPosition};
+use crate::pdo::{RPDO, TPDO};
+use crate::sdo::SdoServer;
+use crate::storage::{ParameterGroup, RestoreDefaultParameters, StorageCallback, StoreParameters};
+use crate::NodeId;
+
+/// Heartbeat producer time in ms
+pub const HEARTBEAT_PRODUCER_TIME: u16 = 0x1017;
+
+/// Maximum number of SDO servers of a [`Node`], including the default one
+pub const MAX_SDO_SERVERS: usize = 4;
+
+/// Access to the communication objects stored inside the object dictionary
+pub trait NodeData {
+    /// The TPDO with the 0-based number `num`
+    ///
+    /// TPDOs are queried with increasing numbers until `None` is returned.
+    fn tpdo(&mut self, num: usize) -> Option<&mut TPDO> {
+        let _ = num;
+        None
+    }
+
+    /// The RPDO with the 0-based number `num`
+    ///
+    /// RPDOs are queried with increasing numbers until `None` is returned.
+    fn rpdo(&mut self, num: usize) -> Option<&mut RPDO> {
+        let _ = num;
+        None
+    }
+
+    /// Object 0x1010, if the device supports storing parameters
+    fn store_parameters(&mut self) -> Option<&mut StoreParameters> {
+        None
+    }
+
+    /// Object 0x1011, if the device supports restoring default parameters
+    fn restore_default_parameters(&mut self) -> Option<&mut RestoreDefaultParameters> {
+        None
+    }
+}
+
+/// All callbacks needed by a [`Node`]
+pub trait NodeCallback<T>:
+    NmtCallback + NmtResetCallback<T> + LssCallback + StorageCallback
+{
+}
+
+impl<T, C: NmtCallback + NmtResetCallback<T> + LssCallback + StorageCallback> NodeCallback<T>
+    for C
+{
+}
+
+/// Destination for frames produced by a [`Node`]
+pub trait Outbox<F> {
+    fn send(&mut self, frame: F);
+}
+
+/// Frames that do not fit are dropped.
+impl<F, const M: usize> Outbox<F> for heapless::Vec<F, M> {
+    fn send(&mut self, frame: F) {
+        self.push(frame).ok();
+    }
+}
+
+#[cfg(feature = "std")]
+impl<F> Outbox<F> for Vec<F> {
+    fn send(&mut self, frame: F) {
+        self.push(frame);
+    }
+}
+
+/// A CANopen device
+///
+/// Routes received frames to the services, enforces the NMT state
+/// and produces boot-up, heartbeat and TPDO messages.
+///
+/// Received RPDOs are written to the mapped objects, which are then
+/// reported by [`ObjectDictionary::take_changed`]. An RPDO that is too short
+/// for its mapping is dropped with the emergency error code 0x8210.
+pub struct Node<T, const N: usize> {
+    pub od: ObjectDictionary<T, N>,
+    pub nmt: Nmt,
+    /// The default SDO server comes first and follows the node-ID,
+    /// further servers keep the COB-IDs they were given.
+    pub sdo_servers: heapless::Vec<SdoServer, MAX_SDO_SERVERS>,
+    pub lss: Option<Lss>,
+    pub emcy: Emcy,
+    now: Duration,
+    last_heartbeat: Option<Duration>,
+}
+
+impl<T: NodeData, const N: usize> Node<T, N> {
+    // SAFETY: 0x80 is below the maximum StandardId 0x7FF
+    pub const SYNC_ID: StandardId = unsafe { StandardId::new_unchecked(0x80) };
+
+    pub fn new(node_id: NodeId, od: ObjectDictionary<T, N>) -> Self {
+        Node {
+            od,
+            nmt: Nmt::new(node_id),
+            sdo_servers: heapless::Vec::from_iter([SdoServer::new(node_id)]),
+            lss: None,
+            emcy: Emcy::new(node_id),
+            now: Duration::ZERO,
+            last_heartbeat: None,
+        }
+    }
+
+    /// Node-ID configured by LSS that is not in use yet
+    pub fn pending_node_id(&self) -> Option<NodeId> {
+        let node_id = self.lss.as_ref()?.node_id?;
+        (node_id != self.nmt.node_id()).then_some(node_id)
+    }
+
+    /// Switch all services to the node-ID configured by LSS.
+    ///
+    /// This is done automatically on "reset communication", as required by CiA 305.
+    pub fn apply_pending_node_id(&mut self) {
+        if let Some(node_id) = self.pending_node_id() {
+            self.set_node_id(node_id);
+        }
+    }
+
+    /// Recalculate all COB-IDs derived from the node-ID.
+    ///
+    /// PDO COB-IDs are only changed if they follow the pre-defined connection set,
+    /// only the default SDO server is moved.
+    pub fn set_node_id(&mut self, node_id: NodeId) {
+        let old_node_id = self.nmt.node_id();
+        self.nmt.set_node_id(node_id);
+        self.sdo_servers[0].set_node_id(node_id);
+        self.emcy.set_node_id(node_id);
+        if let Some(lss) = &mut self.lss {
+            lss.node_id = Some(node_id);
+            lss.set_active_node_id(Some(node_id));
+        }
+        for num in 0.. {
+            let Some(tpdo) = self.od.data.tpdo(num) else {
+                break;
+            };
+            tpdo.set_node_id(old_node_id, node_id);
+        }
+        for num in 0.. {
+            let Some(rpdo) = self.od.data.rpdo(num) else {
+                break;
+            };
+            rpdo.set_node_id(old_node_id, node_id);
+        }
+    }
+
+    /// Handle a received frame.
+    pub fn process<F: Frame>(
+        &mut self,
+        frame: &F,
+        callback: &mut impl NodeCallback<T>,
+        outbox: &mut impl Outbox<F>,
+    ) {
+        let Id::Standard(id) = frame.id() else {
+            return;
+        };
+        if id == Nmt::NMT_REQUEST_ID {
+            if let Some(response) = self.nmt.on_message(frame, callback) {
+                outbox.send(response);
+            }
+            self.on_nmt_request(callback, outbox);
+        } else if id == Self::SYNC_ID {
+            if self.nmt.allows(CommunicationObject::Sync) {
+                self.on_sync(frame.data().first().copied(), outbox);
+            }
+        } else if let Some(server) = self.sdo_servers.iter_mut().find(|s| s.rx_cobid == id) {
+            let mut response = self.nmt.gate(CommunicationObject::Sdo, || {
+                server.on_message(frame, &mut self.od)
+            });
+            // the write to 0x1010 is only confirmed once the parameters are saved
+            if let Err(error) = Self::store_pending_parameters(&mut self.od, callback) {
+                response = Some(server.abort(error.into()).into_frame());
+            }
+            if let Some(response) = response {
+                outbox.send(response);
+            }
+        } else if id == Lss::LSS_REQUEST_ID {
+            if let Some(response) = self
+                .lss
+                .as_mut()
+                .and_then(|lss| lss.on_message(frame, callback))
+            {
+                outbox.send(response);
+            }
+        } else if self.nmt.allows(CommunicationObject::Pdo) {
+            self.on_rpdo(frame, outbox);
+        }
+    }
+
+    /// Advance the time to `now` and send all messages that are due.
+    ///
+    /// The first call loads the stored parameters and sends the boot-up message.
+    pub fn tick<F: Frame>(
+        &mut self,
+        now: Duration,
+        callback: &mut impl NodeCallback<T>,
+        outbox: &mut impl Outbox<F>,
+    ) {
+        self.now = now;
+
+        if self.nmt.state == NmtState::Initialisation && self.nmt.pending_reset().is_none() {
+            self.load_stored_parameters(ParameterGroup::All, callback);
+            outbox.send(self.nmt.boot_up(&mut self.od));
+        }
+
+        if self.nmt.allows(CommunicationObject::ErrorControl) {
+            self.send_heartbeat(outbox);
+        }
+
+        if self.nmt.allows(CommunicationObject::Sdo) {
+            for server in &mut self.sdo_servers {
+                if let Some(abort) = server.on_tick(now, &mut self.od) {
+                    outbox.send(abort.into_frame());
+                }
+            }
+        }
+
+        if self.nmt.allows(CommunicationObject::Pdo) {
+            for num in 0.. {
+                let Some(tpdo) = self.od.data.tpdo(num) else {
+                    break;
+                };
+                if tpdo.on_tick(now) {
+                    self.send_tpdo(num, outbox);
+                }
+            }
+        }
+    }
+
+    /// Trigger all TPDOs mapping the object at `position`,
+    /// e.g. for the objects returned by [`ObjectDictionary::take_changed`].
+    pub fn trigger_tpdos(&mut self, position: OdPosition) {
+        for num in 0.. {
+            let Some(tpdo) = self.od.data.tpdo(num) else {
+                break;
+            };
+            if tpdo.map.contains(position) {
+                tpdo.trigger();
+            }
+        }
+    }
+
+    /// Send an emergency message if allowed in the current NMT state.
+    pub fn emergency<F: Frame>(
+        &mut self,
+        error_code: u16,
+        error_register: u8,
+        manufacturer_specific: [u8; 5],
+        outbox: &mut impl Outbox<F>,
+    ) {
+        if self.nmt.allows(CommunicationObject::Emcy) {
+            let message = self
+                .emcy
+                .message(error_code, error_register, manufacturer_specific);
+            outbox.send(message.into_frame());
+        }
+    }
+
+    fn on_nmt_request<F: Frame>(
+        &mut self,
+        callback: &mut impl NodeCallback<T>,
+        outbox: &mut impl Outbox<F>,
+    ) {
+        if let Some(reset) = self.nmt.pending_reset() {
+            self.apply_pending_node_id();
+            self.abort_sdo_transfers();
+            self.last_heartbeat = None;
+            // the defaults and stored values replace the power-on values of the hooks,
+            // but have to be in place before the boot-up evaluates 0x1F80
+            self.nmt.run_reset_hooks(&mut self.od, callback);
+            self.restore_pending_defaults(reset, callback);
+            self.load_stored_parameters(ParameterGroup::reset_by(reset), callback);
+            outbox.send(self.nmt.boot_up(&mut self.od));
+        } else if !self.nmt.allows(CommunicationObject::Sdo) {
+            self.abort_sdo_transfers();
+        }
+    }
+
+    fn abort_sdo_transfers(&mut self) {
+        for server in &mut self.sdo_servers {
+            server.abort_transfer(&mut self.od);
+        }
+    }
+
+    /// Save the parameters requested through object 0x1010.
+    fn store_pending_parameters(
+        od: &mut ObjectDictionary<T, N>,
+        callback: &mut impl StorageCallback,
+    ) -> Result<(), ODError> {
+        let Some(store) = od.data.store_parameters() else {
+            return Ok(());
+        };
+        let mut result = Ok(());
+        for group in store.take_pending() {
+            let Some(storage) = callback.parameter_storage() else {
+                return Err(ODError::TransferOrStorageError);
+            };
+            result = result.and(od.store_parameters(storage, group));
+        }
+        result
+    }
+
+    /// Restore the defaults requested through object 0x1011.
+    fn restore_pending_defaults(&mut self, reset: NmtReset, callback: &mut impl StorageCallback) {
+        let Some(restore) = self.od.data.restore_default_parameters() else {
+            return;
+        };
+        for group in restore.take_pending(reset) {
+            let cleared = match callback.parameter_storage() {
+                Some(storage) => self.od.clear_parameters(storage, group),
+                None => Ok(()),
+            };
+            let restored = self.od.restore_defaults(group);
+            if let Err(error) = cleared.and(restored) {
+                callback.on_parameter_error(group, error);
+            }
+        }
+    }
+
+    /// Load the stored parameters of `group`.
+    fn load_stored_parameters(
+        &mut self,
+        group: ParameterGroup,
+        callback: &mut impl StorageCallback,
+    ) {
+        let Some(storage) = callback.parameter_storage() else {
+            return;
+        };
+        // values that don't fit anymore keep the value set by the application
+        if let Err(error) = self.od.load_parameters(storage, group) {
+            callback.on_parameter_error(group, error);
+        }
+    }
+
+    fn on_sync<F: Frame>(&mut self, counter: Option<u8>, outbox: &mut impl Outbox<F>) {
+        if !self.nmt.allows(CommunicationObject::Pdo) {
+            return;
+        }
+        for num in 0.. {
+            let Some(rpdo) = self.od.data.rpdo(num) else {
+                break;
+            };
+            if let Some(data) = rpdo.on_sync() {
+                self.write_rpdo(num, &data, outbox);
+            }
+        }
+        for num in 0.. {
+            let Some(tpdo) = self.od.data.tpdo(num) else {
+                break;
+            };
+            if tpdo.on_sync(counter) {
+                self.send_tpdo(num, outbox);
+            }
+        }
+    }
+
+    fn on_rpdo<F: Frame>(&mut self, frame: &F, outbox: &mut impl Outbox<F>) {
+        for num in 0.. {
+            let Some(rpdo) = self.od.data.rpdo(num) else {
+                break;
+            };
+            if rpdo.accepts(frame.id()) {
+                if let Some(data) = rpdo.on_message(frame.data()) {
+                    self.write_rpdo(num, &data, outbox);
+                }
+                break;
+            }
+        }
+    }
+
+    fn write_rpdo<F: Frame>(&mut self, num: usize, data: &[u8], outbox: &mut impl Outbox<F>) {
+        // the RPDO is part of the object dictionary, so the mapping is copied before writing
+        let Some(rpdo) = self.od.data.rpdo(num) else {
+            return;
+        };
+        let map = rpdo.map.clone();
+        // PDOs are not confirmed, so values refused by an object are just dropped
+        if let Err(ODError::TooShort) = map.write_frame(data, &mut self.od) {
+            // PDO not processed due to length error, communication error
+            self.emergency(0x8210, 0x10, [0; 5], outbox);
+        }
+    }
+
+    fn send_heartbeat<F: Frame>(&mut self, outbox: &mut impl Outbox<F>) {
+        let Ok(producer_time) = self.od.get::<u16>(HEARTBEAT_PRODUCER_TIME, 0) else {
+            return;
+        };
+        if producer_time == 0 {
+            return;
+        }
+        let is_due = self.last_heartbeat.is_none_or(|last_heartbeat| {
+            self.now.saturating_sub(last_heartbeat) >= Duration::from_millis(producer_time as u64)
+        });
+        if is_due {
+            self.last_heartbeat = Some(self.now);
+            outbox.send(self.nmt.heartbeat_message());
+        }
+    }
+
+    fn send_tpdo<F: Frame>(&mut self, num: usize, outbox: &mut impl Outbox<F>) {
+        // the TPDO is part of the object dictionary, so the mapping is copied before reading
+        let Some(tpdo) = self.od.data.tpdo(num) else {
+            return;
+        };
+        let id = tpdo.com.cob_id().id;
+        let map = tpdo.map.clone();
+
+        if let Ok(frame) = map.create_frame(id, &mut self.od) {
+            outbox.send(frame);
+        }
+        if let Some(tpdo) = self.od.data.tpdo(num) {
+            tpdo.on_transmission(self.now);
+        }
+    }
+}
@@ -1,25 +1,63 @@
 use core::cmp::Ordering;
 use core::ops::Deref;
 
+use crate::objectdictionary::domain::Domain;
 use crate::objectdictionary::od_cell::OdCell;
 use crate::objectdictionary::{ODError, OdInfo};
 
 mod private {
-    use super::{BasicData, OdCell};
+    use super::{BasicData, Domain, OdCell};
 
     pub trait Sealed {}
     impl<T: BasicData> Sealed for T {}
     impl Sealed for &str {}
     impl Sealed for &[u8] {}
     impl<T> Sealed for OdCell<T> {}
+    impl<T> Sealed for Domain<T> {}
 }
 
 #[diagnostic::on_unimplemented(
-    note = "`{Self}` must either implement `BasicData` or be wrapped in `OdCell`"
+    note = "`{Self}` must either implement `BasicData` or be wrapped in `OdCell` or `Domain`"
 )]
 pub trait DataLink: private::Sealed {
-    fn read(&mut self, index: u16, subindex: u8) -> Result<ReadData, ODError>;
+    fn read(&mut self, index: u16, subindex: u8) -> Result<ReadData<'_>, ODError>;
     fn write(&mut self, data: &WriteData, od_info: OdInfo) -> Result<(), ODError>;
+
+    /// Size in bytes, `None` if it isn't known in advance
+    fn size(&mut self, index: u16, subindex: u8) -> Result<Option<usize>, ODError> {
+        Ok(Some(self.read(index, subindex)?.as_bytes().len()))
+    }
+
+    /// Called before the size and the chunks of a value are read,
+    /// so they all belong to the same value
+    fn begin_read(&mut self) {}
+
+    /// Copy the bytes starting at `offset` into `buf`.
+    ///
+    /// Returns the number of bytes copied,
+    /// which is less than `buf.len()` only at the end of the data.
+    /// Has to be preceded by [`DataLink::begin_read`].
+    fn read_chunk(
+        &mut self,
+        index: u16,
+        subindex: u8,
+        offset: usize,
+        buf: &mut [u8],
+    ) -> Result<usize, ODError> {
+        let data = self.read(index, subindex)?;
+        let chunk = data.as_bytes().get(offset..).unwrap_or_default();
+        let len = chunk.len().min(buf.len());
+        buf[..len].copy_from_slice(&chunk[..len]);
+        Ok(len)
+    }
+
+    /// Called when a segmented SDO transfer of this object is aborted
+    fn abort_transfer(&mut self) {}
+
+    /// Whether only complete values can be written, as for all [`BasicData`]
+    fn is_basic(&self) -> bool {
+        false
+    }
 }
 
 pub trait BasicData {
@@ -28,18 +66,49 @@ pub trait BasicData {
 }
 
 pub trait CustomData {
-    fn read(&self, index: u16, subindex: u8) -> Result<ReadData, ODError>;
+    fn read(&self, index: u16, subindex: u8) -> Result<ReadData<'_>, ODError>;
     fn write(&mut self, data: WriteStream, od_info: OdInfo) -> Result<(), ODError>;
 }
 
+/// DOMAIN objects which are streamed instead of being held in memory as a whole,
+/// e.g. log files or firmware images in external flash
+pub trait DomainData {
+    /// Size in bytes, `None` if it isn't known in advance
+    fn size(&self, index: u16, subindex: u8) -> Option<usize>;
+
+    /// Read the bytes starting at `offset` into `buf`.
+    ///
+    /// Returns the number of bytes read,
+    /// which has to be less than `buf.len()` only at the end of the data.
+    fn read(
+        &mut self,
+        index: u16,
+        subindex: u8,
+        offset: usize,
+        buf: &mut [u8],
+    ) -> Result<usize, ODError>;
+
+    /// Called for every segment of a download.
+    ///
+    /// A segmented download starts with an empty segment carrying the promised size.
+    fn write(&mut self, data: WriteStream, od_info: OdInfo) -> Result<(), ODError>;
+
+    /// Called when a segmented SDO transfer of this object is aborted
+    fn abort_transfer(&mut self) {}
+}
+
 impl<T: BasicData> DataLink for T {
-    fn read(&mut self, index: u16, subindex: u8) -> Result<ReadData, ODError> {
+    fn read(&mut self, index: u16, subindex: u8) -> Result<ReadData<'_>, ODError> {
         Ok(BasicData::read(self, index, subindex)?.into())
     }
 
     fn write(&mut self, data: &WriteData, od_info: OdInfo) -> Result<(), ODError> {
         self.write(BasicWriteData(data), od_info)
     }
+
+    fn is_basic(&self) -> bool {
+        true
+    }
 }
 
 macro_rules! basic_data {
@@ -65,9 +134,12 @@ basic_data!(u8);
 basic_data!(u16);
 basic_data!(u32);
 basic_data!(f32);
+basic_data!(i64);
+basic_data!(u64);
+basic_data!(f64);
 
 impl DataLink for &str {
-    fn read(&mut self, _: u16, _: u8) -> Result<ReadData, ODError> {
+    fn read(&mut self, _: u16, _: u8) -> Result<ReadData<'_>, ODError> {
         Ok(ReadData::Bytes(self.as_bytes()))
     }
 
@@ -77,7 +149,7 @@ impl DataLink for &str {
 }
 
 impl DataLink for &[u8] {
-    fn read(&mut self, _: u16, _: u8) -> Result<ReadData, ODError> {
+    fn read(&mut self, _: u16, _: u8) -> Result<ReadData<'_>, ODError> {
         Ok(ReadData::Bytes(self))
     }
 
@@ -87,14 +159,14 @@ impl DataLink for &[u8] {
 }
 
 impl<T: CustomData> DataLink for OdCell<T> {
-    fn read(&mut self, index: u16, subindex: u8) -> Result<ReadData, ODError> {
-        // TODO locking
+    fn read(&mut self, index: u16, subindex: u8) -> Result<ReadData<'_>, ODError> {
         CustomData::read(self.get(), index, subindex)
     }
 
     fn write(&mut self, data: &WriteData, od_info: OdInfo) -> Result<(), ODError> {
         if data.is_first_segment() {
             self.lock();
+            self.read_unlock();
         } else if !self.is_locked() {
             return Err(ODError::LocalControlError);
         }
@@ -103,10 +175,71 @@ impl<T: CustomData> DataLink for OdCell<T> {
         }
         self.get_mut_unchecked().write(WriteStream(data), od_info)
     }
+
+    /// Locks the value until the last chunk is read,
+    /// so a segmented upload can't mix old and new data or sizes.
+    fn begin_read(&mut self) {
+        self.read_lock();
+    }
+
+    fn read_chunk(
+        &mut self,
+        index: u16,
+        subindex: u8,
+        offset: usize,
+        buf: &mut [u8],
+    ) -> Result<usize, ODError> {
+        if !self.is_read_locked() {
+            // the application changed the value in the meantime
+            return Err(ODError::LocalControlError);
+        }
+        let data = CustomData::read(self.get(), index, subindex)?;
+        let data = data.as_bytes();
+        let chunk = data.get(offset..).unwrap_or_default();
+        let len = chunk.len().min(buf.len());
+        buf[..len].copy_from_slice(&chunk[..len]);
+        if offset + len >= data.len() {
+            self.read_unlock();
+        }
+        Ok(len)
+    }
+
+    fn abort_transfer(&mut self) {
+        self.read_unlock();
+    }
+}
+
+impl<T: DomainData> DataLink for Domain<T> {
+    /// DOMAIN objects can only be read in chunks
+    fn read(&mut self, _: u16, _: u8) -> Result<ReadData<'_>, ODError> {
+        Err(ODError::UnsupportedAccess)
+    }
+
+    fn write(&mut self, data: &WriteData, od_info: OdInfo) -> Result<(), ODError> {
+        self.get_mut().write(WriteStream(data), od_info)
+    }
+
+    fn size(&mut self, index: u16, subindex: u8) -> Result<Option<usize>, ODError> {
+        Ok(self.get().size(index, subindex))
+    }
+
+    fn read_chunk(
+        &mut self,
+        index: u16,
+        subindex: u8,
+        offset: usize,
+        buf: &mut [u8],
+    ) -> Result<usize, ODError> {
+        self.get_mut().read(index, subindex, offset, buf)
+    }
+
+    fn abort_transfer(&mut self) {
+        self.get_mut().abort_transfer();
+    }
 }
 
 impl<const N: usize> CustomData for [u8; N] {
-    fn read(&self, _: u16, _: u8) -> Result<ReadData, ODError> {
+    fn read(&self, _: u16, _: u8) -> Result<ReadData<'_>, ODError> {
         Ok(self[..].into())
     }
 
@@ -116,17 +249,71 @@ impl<const N: usize> CustomData for [u8; N] {
     }
 }
 
+/// OCTET_STRING with a variable length of up to `N` bytes
+impl<const N: usize> CustomData for heapless::Vec<u8, N> {
+    fn read(&self, _: u16, _: u8) -> Result<ReadData<'_>, ODError> {
+        Ok(self[..].into())
+    }
+
+    fn write(&mut self, data: WriteStream, _: OdInfo) -> Result<(), ODError> {
+        if data.promised_size.is_some_and(|size| size > N) {
+            return Err(ODError::TooLong);
+        }
+        if data.offset > self.len() {
+            return Err(ODError::LocalControlError);
+        }
+        self.truncate(data.offset);
+        self.extend_from_slice(data.new_data)
+            .map_err(|_| ODError::TooLong)
+    }
+}
+
+/// VISIBLE_STRING with a variable length of up to `N` characters
+///
+/// Only ASCII characters are accepted, so the string stays valid even
+/// if a segmented download is aborted half way through.
+impl<const N: usize> CustomData for heapless::String<N> {
+    fn read(&self, _: u16, _: u8) -> Result<ReadData<'_>, ODError> {
+        Ok(self.as_str().into())
+    }
+
+    fn write(&mut self, data: WriteStream, _: OdInfo) -> Result<(), ODError> {
+        if data.promised_size.is_some_and(|size| size > N) {
+            return Err(ODError::TooLong);
+        }
+        if data.offset > self.len() {
+            return Err(ODError::LocalControlError);
+        }
+        if !data.new_data.is_ascii() {
+            return Err(ODError::InvalidValue);
+        }
+        self.truncate(data.offset);
+        let new_data = core::str::from_utf8(data.new_data).unwrap(); // Infallible
+        self.push_str(new_data).map_err(|_| ODError::TooLong)
+    }
+}
+
 pub enum ReadData<'a> {
     B1([u8; 1]),
     B2([u8; 2]),
+    B3([u8; 3]),
     B4([u8; 4]),
+    B5([u8; 5]),
+    B6([u8; 6]),
+    B7([u8; 7]),
+    B8([u8; 8]),
     Bytes(&'a [u8]),
 }
 
 pub enum BasicReadData {
     B1([u8; 1]),
     B2([u8; 2]),
+    B3([u8; 3]),
     B4([u8; 4]),
+    B5([u8; 5]),
+    B6([u8; 6]),
+    B7([u8; 7]),
+    B8([u8; 8]),
 }
 
 impl From<BasicReadData> for ReadData<'_> {
@@ -134,7 +321,12 @@ impl From<BasicReadData> for ReadData<'_> {
         match value {
             BasicReadData::B1(val) => ReadData::B1(val),
             BasicReadData::B2(val) => ReadData::B2(val),
+            BasicReadData::B3(val) => ReadData::B3(val),
             BasicReadData::B4(val) => ReadData::B4(val),
+            BasicReadData::B5(val) => ReadData::B5(val),
+            BasicReadData::B6(val) => ReadData::B6(val),
+            BasicReadData::B7(val) => ReadData::B7(val),
+            BasicReadData::B8(val) => ReadData::B8(val),
         }
     }
 }
@@ -144,7 +336,12 @@ impl ReadData<'_> {
         match self {
             ReadData::B1(val) => val,
             ReadData::B2(val) => val,
+            ReadData::B3(val) => val,
             ReadData::B4(val) => val,
+            ReadData::B5(val) => val,
+            ReadData::B6(val) => val,
+            ReadData::B7(val) => val,
+            ReadData::B8(val) => val,
             ReadData::Bytes(val) => val,
         }
     }
@@ -188,6 +385,9 @@ read_data_from_impl!(i16, ReadData::B2, BasicReadData::B2);
 read_data_from_impl!(u32, ReadData::B4, BasicReadData::B4);
 read_data_from_impl!(i32, ReadData::B4, BasicReadData::B4);
 read_data_from_impl!(f32, ReadData::B4, BasicReadData::B4);
+read_data_from_impl!(u64, ReadData::B8, BasicReadData::B8);
+read_data_from_impl!(i64, ReadData::B8, BasicReadData::B8);
+read_data_from_impl!(f64, ReadData::B8, BasicReadData::B8);
 
 impl From<bool> for BasicReadData {
     #[inline]
@@ -215,7 +415,7 @@ pub struct WriteData<'a> {
     pub is_last_segment: bool,
 }
 
-pub struct BasicWriteData<'a>(&'a WriteData<'a>);
+pub struct BasicWriteData<'a>(pub(crate) &'a WriteData<'a>);
 
 impl BasicWriteData<'_> {
     pub fn index(&self) -> u16 {
@@ -327,6 +527,89 @@ try_from_impl!(i16);
 try_from_impl!(u32);
 try_from_impl!(i32);
 try_from_impl!(f32);
+try_from_impl!(u64);
+try_from_impl!(i64);
+try_from_impl!(f64);
+
+/// # Derive macro for `OdValue`
+///
+/// Stores a fieldless enum with `#[repr(u8)]` (or another integer) or an integer newtype
+/// as its integer. Unknown discriminants and set `reserved` bits are rejected with
+/// [`ODError::InvalidValue`]. The derive of `OdData` can't tell the CANopen type of the
+/// field, so `typ` must be given to map it into a PDO.
+///
+/// ```
+/// use canopen::objectdictionary::datalink::OdValue;
+/// use canopen::objectdictionary::{ODError, OdData};
+///
+/// #[derive(OdValue, Clone, Copy, PartialEq, Debug)]
+/// #[repr(i8)]
+/// enum Mode {
+///     Position = 1,
+///     Velocity = 3,
+/// }
+///
+/// #[derive(OdValue, Clone, Copy, PartialEq, Debug)]
+/// #[canopen(reserved = 0xFF00)]
+/// struct Status(u16);
+///
+/// #[derive(OdData)]
+/// struct Data {
+///     #[canopen(index = 0x6060, typ = i8)]
+///     mode: Mode,
+///     #[canopen(index = 0x6041, typ = u16)]
+///     status: Status,
+/// }
+///
+/// let mut od = Data {
+///     mode: Mode::Position,
+///     status: Status(0),
+/// }
+/// .into_od();
+/// od.write(0x6060, 0, &[3]).unwrap();
+/// assert_eq!(od.data.mode, Mode::Velocity);
+/// assert_eq!(od.write(0x6060, 0, &[2]), Err(ODError::InvalidValue));
+/// assert_eq!(od.set(0x6041, 0, Status(0x100)), Err(ODError::InvalidValue));
+/// ```
+pub use canopen_derive::OdValue;
+
+/// Values that can be accessed with [`ObjectDictionary::get`](crate::ObjectDictionary::get)
+/// and [`ObjectDictionary::set`](crate::ObjectDictionary::set)
+pub trait OdValue: Into<BasicReadData> {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, ODError>;
+}
+
+macro_rules! od_value {
+    ($typ:ty) => {
+        impl OdValue for $typ {
+            fn from_bytes(bytes: &[u8]) -> Result<Self, ODError> {
+                check_size(bytes.len(), core::mem::size_of::<$typ>())?;
+                Ok(<$typ>::from_le_bytes(bytes.try_into().unwrap())) // Infallible
+            }
+        }
+    };
+}
+
+od_value!(u8);
+od_value!(i8);
+od_value!(u16);
+od_value!(i16);
+od_value!(u32);
+od_value!(i32);
+od_value!(f32);
+od_value!(u64);
+od_value!(i64);
+od_value!(f64);
+
+impl OdValue for bool {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, ODError> {
+        match u8::from_bytes(bytes)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(ODError::InvalidValue),
+        }
+    }
+}
 
 impl<'a> TryFrom<BasicWriteData<'a>> for bool {
     type Error = ODError;
@@ -340,7 +623,7 @@ impl<'a> TryFrom<BasicWriteData<'a>> for bool {
     }
 }
 
-fn check_size(given: usize, expected: usize) -> Result<(), ODError> {
+pub(crate) fn check_size(given: usize, expected: usize) -> Result<(), ODError> {
     match given.cmp(&expected) {
         Ordering::Less => Err(ODError::TooShort),
         Ordering::Greater => Err(ODError::TooLong),
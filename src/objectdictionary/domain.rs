@@ -0,0 +1,16 @@
+/// Wrapper for objects implementing [`DomainData`](crate::objectdictionary::datalink::DomainData)
+pub struct Domain<T> {
+    pub value: T,
+}
+
+impl<T> Domain<T> {
+    pub fn new(value: T) -> Self {
+        Self { value }
+    }
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
@@ -0,0 +1,160 @@
+//! Integer types of CiA 301 without a Rust equivalent
+//!
+//! The values are stored in the next larger Rust integer and transmitted
+//! with their CANopen size.
+
+use crate::objectdictionary::datalink::{
+    check_size, BasicData, BasicReadData, BasicWriteData, OdValue, ReadData,
+};
+use crate::objectdictionary::{ODError, OdInfo};
+
+macro_rules! odd_integer {
+    ($(#[$meta:meta])* $name:ident, $inner:ty, $bytes:literal, $variant:ident) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+        pub struct $name($inner);
+
+        impl $name {
+            pub const BITS: u32 = $bytes * 8;
+            const SHIFT: u32 = <$inner>::BITS - Self::BITS;
+            pub const MIN: Self = $name(<$inner>::MIN >> Self::SHIFT);
+            pub const MAX: Self = $name(<$inner>::MAX >> Self::SHIFT);
+
+            /// `None` if `value` is out of range
+            pub const fn new(value: $inner) -> Option<Self> {
+                if (value << Self::SHIFT) >> Self::SHIFT == value {
+                    Some($name(value))
+                } else {
+                    None
+                }
+            }
+
+            pub const fn get(self) -> $inner {
+                self.0
+            }
+
+            pub fn to_le_bytes(self) -> [u8; $bytes] {
+                let mut bytes = [0; $bytes];
+                bytes.copy_from_slice(&self.0.to_le_bytes()[..$bytes]);
+                bytes
+            }
+
+            pub fn from_le_bytes(bytes: [u8; $bytes]) -> Self {
+                let mut buf = [0; core::mem::size_of::<$inner>()];
+                buf[..$bytes].copy_from_slice(&bytes);
+                // shifting back extends the sign of signed types
+                $name((<$inner>::from_le_bytes(buf) << Self::SHIFT) >> Self::SHIFT)
+            }
+        }
+
+        impl From<$name> for $inner {
+            fn from(val: $name) -> Self {
+                val.0
+            }
+        }
+
+        impl From<$name> for BasicReadData {
+            #[inline]
+            fn from(val: $name) -> Self {
+                BasicReadData::$variant(val.to_le_bytes())
+            }
+        }
+
+        impl From<$name> for ReadData<'_> {
+            #[inline]
+            fn from(val: $name) -> Self {
+                ReadData::$variant(val.to_le_bytes())
+            }
+        }
+
+        impl OdValue for $name {
+            fn from_bytes(bytes: &[u8]) -> Result<Self, ODError> {
+                check_size(bytes.len(), $bytes)?;
+                Ok($name::from_le_bytes(bytes.try_into().unwrap())) // Infallible
+            }
+        }
+
+        impl<'a> TryFrom<BasicWriteData<'a>> for $name {
+            type Error = ODError;
+
+            fn try_from(data: BasicWriteData<'a>) -> Result<Self, Self::Error> {
+                if let Some(size) = data.0.promised_size {
+                    check_size(size, $bytes)?;
+                }
+                $name::from_bytes(data.0.new_data)
+            }
+        }
+
+        impl BasicData for $name {
+            fn read(&mut self, _: u16, _: u8) -> Result<BasicReadData, ODError> {
+                Ok(BasicReadData::from(*self))
+            }
+
+            fn write(&mut self, data: BasicWriteData, _: OdInfo) -> Result<(), ODError> {
+                *self = data.try_into()?;
+                Ok(())
+            }
+        }
+    };
+}
+
+odd_integer!(
+    /// UNSIGNED24
+    U24, u32, 3, B3
+);
+odd_integer!(
+    /// INTEGER24
+    I24, i32, 3, B3
+);
+odd_integer!(
+    /// UNSIGNED40
+    U40, u64, 5, B5
+);
+odd_integer!(
+    /// INTEGER40
+    I40, i64, 5, B5
+);
+odd_integer!(
+    /// UNSIGNED48
+    U48, u64, 6, B6
+);
+odd_integer!(
+    /// INTEGER48
+    I48, i64, 6, B6
+);
+odd_integer!(
+    /// UNSIGNED56
+    U56, u64, 7, B7
+);
+odd_integer!(
+    /// INTEGER56
+    I56, i64, 7, B7
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_range() {
+        assert_eq!(U24::MAX.get(), 0xFF_FFFF);
+        assert_eq!(I24::MIN.get(), -0x80_0000);
+        assert_eq!(I56::MAX.get(), 0x7F_FFFF_FFFF_FFFF);
+        assert_eq!(U24::new(0x100_0000), None);
+        assert_eq!(I24::new(0x80_0000), None);
+        assert_eq!(I24::new(-0x80_0001), None);
+    }
+
+    #[test]
+    fn test_bytes() {
+        let value = I24::new(-2).unwrap();
+        assert_eq!(value.to_le_bytes(), [0xFE, 0xFF, 0xFF]);
+        assert_eq!(I24::from_le_bytes([0xFE, 0xFF, 0xFF]), value);
+        assert_eq!(U24::from_le_bytes([0xFE, 0xFF, 0xFF]).get(), 0xFF_FFFE);
+        assert_eq!(
+            I40::from_le_bytes([1, 0, 0, 0, 0x80]).get(),
+            -0x80_0000_0000 + 1
+        );
+        assert_eq!(U48::from_bytes(&[1, 2, 3, 4, 5]), Err(ODError::TooShort));
+    }
+}
@@ -1,15 +1,19 @@
 use crate::meta::{from_raw_parts_mut, DynMetadata};
 use crate::objectdictionary::datalink::{
-    BasicData, BasicReadData, BasicWriteData, DataLink, ReadData,
+    BasicData, BasicReadData, BasicWriteData, DataLink, OdValue, ReadData, WriteData,
 };
 
 use crate::sdo::SDOAbortCode;
+use crate::storage::{ParameterGroup, ParameterStorage, MAX_PARAMETER_SIZE};
 
 pub mod datalink;
+pub mod domain;
+pub mod integer;
 pub mod object;
 pub mod od_cell;
+pub mod record;
 
-use crate::objectdictionary::object::{ObjectFlags, ObjectInfo};
+use crate::objectdictionary::object::{Limits, ObjectFlags, ObjectInfo, ObjectMetadata, OdEntry};
 
 /// # Derive macro for `OdData`
 ///
@@ -33,23 +37,40 @@ pub trait OdData {
     fn into_od(self) -> Self::OdType;
 }
 
+/// Bytes of each value shown by [`ObjectDictionary::report`]
+pub const REPORT_VALUE_SIZE: usize = 16;
+
 pub struct ObjectDictionary<T, const N: usize> {
     indices: [u16; N],
     subindices: [u8; N],
     pdo_sizes: [ObjectFlags; N],
     offsets: [usize; N],
     vtables: [DynMetadata<dyn DataLink>; N],
+    metadata: &'static [ObjectMetadata; N],
+    /// Sorted by position, only objects with limits are listed
+    limits: &'static [(OdPosition, Limits)],
+    /// Sorted by position, only objects with a default value are listed
+    defaults: &'static [(OdPosition, &'static [u8])],
+    /// Runs the `validate` functions of the derive
+    validate: fn(&T, OdPosition, &[u8]) -> Result<(), ODError>,
+    /// Objects written since the last `take_changed`
+    changed: [bool; N],
     pub data: T,
 }
 
 impl<T, const N: usize> ObjectDictionary<T, N> {
     #[doc(hidden)]
+    #[allow(clippy::too_many_arguments)] // only called by the derive macro
     pub unsafe fn new(
         indices: [u16; N],
         subindices: [u8; N],
         pdo_sizes: [ObjectFlags; N],
         offsets: [usize; N],
         vtables: [DynMetadata<dyn DataLink>; N],
+        metadata: &'static [ObjectMetadata; N],
+        limits: &'static [(OdPosition, Limits)],
+        defaults: &'static [(OdPosition, &'static [u8])],
+        validate: fn(&T, OdPosition, &[u8]) -> Result<(), ODError>,
         data: T,
     ) -> Self {
         ObjectDictionary {
@@ -58,20 +79,354 @@ impl<T, const N: usize> ObjectDictionary<T, N> {
             pdo_sizes,
             offsets,
             vtables,
+            metadata,
+            limits,
+            defaults,
+            validate,
+            changed: [false; N],
             data,
         }
     }
 
     pub fn find(&mut self, index: u16, subindex: u8) -> Result<&mut dyn DataLink, ODError> {
         let position = self.search(index, subindex)?;
-        Ok(self.get(position))
+        Ok(self.link(position))
     }
 
-    pub fn read(&mut self, index: u16, subindex: u8) -> Result<ReadData, ODError> {
+    pub fn read(&mut self, index: u16, subindex: u8) -> Result<ReadData<'_>, ODError> {
         self.find(index, subindex)?.read(index, subindex)
     }
 
-    pub(crate) fn get(&mut self, position: OdPosition) -> &mut dyn DataLink {
+    /// Write `data` to an object in a single transfer.
+    ///
+    /// Read-only objects, values out of the object's [`Limits`] and values
+    /// refused by its `validate` function are rejected like an SDO download would be.
+    pub fn write(&mut self, index: u16, subindex: u8, data: &[u8]) -> Result<(), ODError> {
+        let position = self.search(index, subindex)?;
+        if self.flags(position).is_read_only() {
+            return Err(ODError::ReadOnlyError);
+        }
+        let data = WriteData {
+            index,
+            subindex,
+            new_data: data,
+            offset: 0,
+            promised_size: Some(data.len()),
+            is_last_segment: true,
+        };
+        self.write_position(position, &data)
+    }
+
+    /// Read an object as `V`.
+    ///
+    /// ```
+    /// use canopen::objectdictionary::OdData;
+    ///
+    /// #[derive(OdData)]
+    /// struct Data {
+    ///     #[canopen(index = 0x2000)]
+    ///     number: u16,
+    /// }
+    ///
+    /// let mut od = Data { number: 0 }.into_od();
+    /// od.set(0x2000, 0, 42u16).unwrap();
+    /// assert_eq!(od.get::<u16>(0x2000, 0), Ok(42));
+    /// ```
+    pub fn get<V: OdValue>(&mut self, index: u16, subindex: u8) -> Result<V, ODError> {
+        let position = self.search(index, subindex)?;
+        if self.flags(position).is_write_only() {
+            return Err(ODError::WriteOnlyError);
+        }
+        V::from_bytes(self.link(position).read(index, subindex)?.as_bytes())
+    }
+
+    /// Write `value` to an object, see [`ObjectDictionary::write`].
+    pub fn set<V: OdValue>(&mut self, index: u16, subindex: u8, value: V) -> Result<(), ODError> {
+        let data = ReadData::from(value.into());
+        self.write(index, subindex, data.as_bytes())
+    }
+
+    /// The range of values accepted by an object, `None` if it isn't limited
+    pub fn limits(&self, index: u16, subindex: u8) -> Result<Option<Limits>, ODError> {
+        let position = self.search(index, subindex)?;
+        Ok(self.limits_at(position))
+    }
+
+    fn limits_at(&self, position: OdPosition) -> Option<Limits> {
+        lookup(self.limits, position).copied()
+    }
+
+    /// The encoded default value of an object, `None` if it has none
+    pub fn default_value(&self, index: u16, subindex: u8) -> Result<Option<&[u8]>, ODError> {
+        let position = self.search(index, subindex)?;
+        Ok(lookup(self.defaults, position).copied())
+    }
+
+    /// Write the default values of all objects in `group`, regardless of their access type.
+    ///
+    /// Objects without a default value are left unchanged.
+    /// All objects are restored, even if one of them fails.
+    pub fn restore_defaults(&mut self, group: ParameterGroup) -> Result<(), ODError> {
+        let mut result = Ok(());
+        for &(position, default) in self.defaults {
+            let index = self.indices[position.0];
+            if !group.contains(index) {
+                continue;
+            }
+            let data = WriteData {
+                index,
+                subindex: self.subindices[position.0],
+                new_data: default,
+                offset: 0,
+                promised_size: Some(default.len()),
+                is_last_segment: true,
+            };
+            if let Err(error) = self.write_position(position, &data) {
+                result = result.and(Err(error));
+            }
+        }
+        result
+    }
+
+    /// Save the values of all persistent objects in `group`, stopping at the first error.
+    pub fn store_parameters(
+        &mut self,
+        storage: &mut (impl ParameterStorage + ?Sized),
+        group: ParameterGroup,
+    ) -> Result<(), ODError> {
+        // one extra byte to detect values that are too large
+        let mut buf = [0; MAX_PARAMETER_SIZE + 1];
+        for position in (0..N).map(OdPosition) {
+            if !self.is_parameter(position, group) {
+                continue;
+            }
+            let (index, subindex) = (self.indices[position.0], self.subindices[position.0]);
+            let link = self.link(position);
+            link.begin_read();
+            let size = link
+                .read_chunk(index, subindex, 0, &mut buf)
+                .inspect_err(|_| link.abort_transfer())?;
+            if size > MAX_PARAMETER_SIZE {
+                link.abort_transfer();
+                return Err(ODError::OutOfMemory);
+            }
+            storage.save(index, subindex, &buf[..size])?;
+        }
+        storage.commit()
+    }
+
+    /// Write the saved values of all persistent objects in `group`,
+    /// regardless of their access type.
+    ///
+    /// Objects without a saved value are left unchanged.
+    /// All objects are loaded, even if one of them fails.
+    pub fn load_parameters(
+        &mut self,
+        storage: &mut (impl ParameterStorage + ?Sized),
+        group: ParameterGroup,
+    ) -> Result<(), ODError> {
+        let mut buf = [0; MAX_PARAMETER_SIZE];
+        let mut result = Ok(());
+        for position in (0..N).map(OdPosition) {
+            if !self.is_parameter(position, group) {
+                continue;
+            }
+            let (index, subindex) = (self.indices[position.0], self.subindices[position.0]);
+            let size = match storage.load(index, subindex, &mut buf) {
+                Ok(Some(size)) => size,
+                Ok(None) => continue,
+                Err(error) => {
+                    result = result.and(Err(error));
+                    continue;
+                }
+            };
+            let data = WriteData {
+                index,
+                subindex,
+                new_data: &buf[..size],
+                offset: 0,
+                promised_size: Some(size),
+                is_last_segment: true,
+            };
+            if let Err(error) = self.write_position(position, &data) {
+                result = result.and(Err(error));
+            }
+        }
+        result
+    }
+
+    /// Remove the saved values of all persistent objects in `group`.
+    pub fn clear_parameters(
+        &mut self,
+        storage: &mut (impl ParameterStorage + ?Sized),
+        group: ParameterGroup,
+    ) -> Result<(), ODError> {
+        for position in (0..N).map(OdPosition) {
+            if !self.is_parameter(position, group) {
+                continue;
+            }
+            storage.remove(self.indices[position.0], self.subindices[position.0])?;
+        }
+        storage.commit()
+    }
+
+    /// Whether the object at `position` is persistent and part of `group`
+    fn is_parameter(&self, position: OdPosition, group: ParameterGroup) -> bool {
+        self.flags(position).is_persistent() && group.contains(self.indices[position.0])
+    }
+
+    /// Take the next object whose value was written since it was last taken.
+    ///
+    /// Every successful write marks the object, whether it comes from an SDO download,
+    /// [`ObjectDictionary::write`] or loading parameters. Segmented downloads only mark
+    /// it with the last segment. Objects are returned in the order of the dictionary
+    /// and only once, no matter how often they were written.
+    ///
+    /// ```
+    /// use canopen::objectdictionary::OdData;
+    ///
+    /// #[derive(OdData)]
+    /// struct Data {
+    ///     #[canopen(index = 0x2000)]
+    ///     setpoint: u16,
+    /// }
+    ///
+    /// let mut od = Data { setpoint: 0 }.into_od();
+    /// od.set(0x2000, 0, 42u16).unwrap();
+    /// while let Some(info) = od.take_changed() {
+    ///     assert_eq!(info.index, 0x2000);
+    /// }
+    /// ```
+    pub fn take_changed(&mut self) -> Option<ObjectInfo> {
+        let position = self.changed.iter().position(|&changed| changed)?;
+        self.changed[position] = false;
+        Some(self.entry_at(OdPosition(position)).info)
+    }
+
+    /// Forget all writes, e.g. after applying the whole configuration at once.
+    pub fn clear_changed(&mut self) {
+        self.changed = [false; N];
+    }
+
+    /// Number of objects, counting every subindex
+    pub fn len(&self) -> usize {
+        N
+    }
+
+    pub fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// All objects, sorted by index and subindex
+    pub fn entries(&self) -> impl Iterator<Item = OdEntry> + '_ {
+        (0..N).map(|position| self.entry_at(OdPosition(position)))
+    }
+
+    pub fn entry(&self, index: u16, subindex: u8) -> Result<OdEntry, ODError> {
+        let position = self.search(index, subindex)?;
+        Ok(self.entry_at(position))
+    }
+
+    fn entry_at(&self, position: OdPosition) -> OdEntry {
+        let metadata = self.metadata[position.0];
+        OdEntry {
+            info: ObjectInfo {
+                index: self.indices[position.0],
+                subindex: self.subindices[position.0],
+                flags: self.flags(position),
+                od_position: position,
+            },
+            name: metadata.name,
+            data_type: metadata.data_type,
+            limits: self.limits_at(position),
+            default: lookup(self.defaults, position).copied(),
+        }
+    }
+
+    /// Write one line per object with its name, access type, size and value, e.g.
+    /// `0x2000sub0 speed rw 4 bytes: 01 02 03 04`.
+    ///
+    /// Values longer than [`REPORT_VALUE_SIZE`] are cut off,
+    /// write-only objects are not read.
+    pub fn report(&mut self, out: &mut impl core::fmt::Write) -> core::fmt::Result {
+        let mut buf = [0; REPORT_VALUE_SIZE + 1];
+        for position in (0..N).map(OdPosition) {
+            let (index, subindex) = (self.indices[position.0], self.subindices[position.0]);
+            let flags = self.flags(position);
+            let name = self.metadata[position.0].name;
+            let access = flags.access_type().as_str();
+            write!(out, "0x{index:04X}sub{subindex} {name} {access}")?;
+            if flags.is_write_only() {
+                writeln!(out)?;
+                continue;
+            }
+            let link = self.link(position);
+            link.begin_read();
+            let len = match link.read_chunk(index, subindex, 0, &mut buf) {
+                Ok(len) => len,
+                Err(error) => {
+                    link.abort_transfer();
+                    writeln!(out, " error: {error:?}")?;
+                    continue;
+                }
+            };
+            let is_cut_off = len > REPORT_VALUE_SIZE;
+            match link.size(index, subindex) {
+                Ok(Some(size)) => write!(out, " {size} bytes:")?,
+                _ => write!(out, " ? bytes:")?,
+            }
+            for byte in &buf[..len.min(REPORT_VALUE_SIZE)] {
+                write!(out, " {byte:02X}")?;
+            }
+            if is_cut_off {
+                // releases an OdCell locked by the partial read
+                link.abort_transfer();
+                write!(out, " ...")?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// Write to the object at `position`,
+    /// checking the value against its limits and `validate` function.
+    ///
+    /// The checks need the complete value, so objects with limits or a `validate`
+    /// function refuse partial writes. The SDO server buffers the segments of
+    /// basic objects and writes them at once.
+    pub(crate) fn write_position(
+        &mut self,
+        position: OdPosition,
+        data: &WriteData,
+    ) -> Result<(), ODError> {
+        let flags = self.flags(position);
+        let limits = self.limits_at(position);
+        if limits.is_some() || flags.is_validated() {
+            if !data.is_first_segment() || !data.is_last_segment {
+                return Err(ODError::UnsupportedAccess);
+            }
+            let size = flags.pdo_size().map(|size| size.get() as usize);
+            if let Some(limits) = limits {
+                // values of the wrong size are rejected by the object itself
+                if size == Some(data.new_data.len()) {
+                    limits.check(data.new_data)?;
+                }
+            }
+            (self.validate)(&self.data, position, data.new_data)?;
+        }
+        let (link, info) = self.get_plus(position);
+        link.write(data, info)?;
+        if data.is_last_segment {
+            self.changed[position.0] = true;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn flags(&self, position: OdPosition) -> ObjectFlags {
+        self.pdo_sizes[position.0]
+    }
+
+    pub(crate) fn link(&mut self, position: OdPosition) -> &mut dyn DataLink {
         let mut data_ptr = &mut self.data as *mut T as *mut ();
         unsafe {
             data_ptr = data_ptr.byte_add(self.offsets[position.0]);
@@ -81,7 +436,7 @@ impl<T, const N: usize> ObjectDictionary<T, N> {
         unsafe { &mut *fat_ptr }
     }
 
-    pub(crate) fn get_plus(&mut self, position: OdPosition) -> (&mut dyn DataLink, OdInfo) {
+    pub(crate) fn get_plus(&mut self, position: OdPosition) -> (&mut dyn DataLink, OdInfo<'_>) {
         let mut data_ptr = &mut self.data as *mut T as *mut ();
         unsafe {
             data_ptr = data_ptr.byte_add(self.offsets[position.0]);
@@ -106,6 +461,12 @@ impl<T, const N: usize> ObjectDictionary<T, N> {
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct OdPosition(pub usize);
 
+/// Find the entry of `position` in a table sorted by position
+fn lookup<V>(table: &[(OdPosition, V)], position: OdPosition) -> Option<&V> {
+    let i = table.binary_search_by_key(&position.0, |(p, _)| p.0).ok()?;
+    Some(&table[i].1)
+}
+
 pub struct OdInfo<'a> {
     indices: &'a [u16],
     subindices: &'a [u8],
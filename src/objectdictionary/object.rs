@@ -1,4 +1,4 @@
-use crate::objectdictionary::OdPosition;
+use crate::objectdictionary::{ODError, OdPosition};
 
 #[derive(Clone, Debug)]
 pub struct ObjectInfo {
@@ -8,12 +8,27 @@ pub struct ObjectInfo {
     pub od_position: OdPosition,
 }
 
+/// Everything known about an object without reading its value
+#[derive(Clone, Debug)]
+pub struct OdEntry {
+    pub info: ObjectInfo,
+    /// `ParameterName` in an EDS
+    pub name: &'static str,
+    pub data_type: Option<DataType>,
+    /// Range of values accepted on writes
+    pub limits: Option<Limits>,
+    /// Encoded `default` value
+    pub default: Option<&'static [u8]>,
+}
+
 #[derive(Clone, Copy)]
 pub struct ObjectFlags(u8);
 
 impl ObjectFlags {
     const READ_ONLY_FLAG: u8 = 0b0001_0000;
     const WRITE_ONLY_FLAG: u8 = 0b0010_0000;
+    const PERSISTENT_FLAG: u8 = 0b0100_0000;
+    const VALIDATED_FLAG: u8 = 0b1000_0000;
 
     pub const fn empty() -> Self {
         ObjectFlags(0)
@@ -34,8 +49,16 @@ impl ObjectFlags {
         self.0 |= Self::WRITE_ONLY_FLAG;
         self
     }
+    pub const fn set_persistent(mut self) -> Self {
+        self.0 |= Self::PERSISTENT_FLAG;
+        self
+    }
+    pub const fn set_validated(mut self) -> Self {
+        self.0 |= Self::VALIDATED_FLAG;
+        self
+    }
     pub const fn set_pdo_size(mut self, size: PdoSize) -> Self {
-        self.0 |= size as u8;
+        self.0 = (self.0 & !0b0000_1111) | size as u8;
         self
     }
 
@@ -45,15 +68,26 @@ impl ObjectFlags {
     pub const fn is_write_only(&self) -> bool {
         self.0 & Self::WRITE_ONLY_FLAG != 0
     }
-    pub const fn pdo_size(&self) -> Option<PdoSize> {
-        match self.0 & 0b0000_0111 {
-            0 => None,
-            1 => Some(PdoSize::One),
-            2 => Some(PdoSize::Two),
-            4 => Some(PdoSize::Four),
-            _ => unsafe { core::hint::unreachable_unchecked() }, // Other bit patterns cannot be created
+    pub const fn access_type(&self) -> AccessType {
+        if self.is_read_only() {
+            AccessType::ReadOnly
+        } else if self.is_write_only() {
+            AccessType::WriteOnly
+        } else {
+            AccessType::ReadWrite
         }
     }
+    /// Saved by object 0x1010, see [`storage`](crate::storage)
+    pub const fn is_persistent(&self) -> bool {
+        self.0 & Self::PERSISTENT_FLAG != 0
+    }
+    /// Has a `validate` function, see [`OdData`](crate::objectdictionary::OdData)
+    pub const fn is_validated(&self) -> bool {
+        self.0 & Self::VALIDATED_FLAG != 0
+    }
+    pub const fn pdo_size(&self) -> Option<PdoSize> {
+        PdoSize::new(self.0 & 0b0000_1111)
+    }
 }
 
 impl Default for ObjectFlags {
@@ -67,6 +101,8 @@ impl core::fmt::Debug for ObjectFlags {
         f.debug_struct("ObjectFlags")
             .field("read_only", &self.is_read_only())
             .field("write_only", &self.is_write_only())
+            .field("persistent", &self.is_persistent())
+            .field("validated", &self.is_validated())
             .field("pdo_size", &self.pdo_size())
             .finish()
     }
@@ -77,7 +113,12 @@ impl core::fmt::Debug for ObjectFlags {
 pub enum PdoSize {
     One = 1,
     Two = 2,
+    Three = 3,
     Four = 4,
+    Five = 5,
+    Six = 6,
+    Seven = 7,
+    Eight = 8,
 }
 
 impl PdoSize {
@@ -85,7 +126,12 @@ impl PdoSize {
         match val {
             1 => Some(PdoSize::One),
             2 => Some(PdoSize::Two),
+            3 => Some(PdoSize::Three),
             4 => Some(PdoSize::Four),
+            5 => Some(PdoSize::Five),
+            6 => Some(PdoSize::Six),
+            7 => Some(PdoSize::Seven),
+            8 => Some(PdoSize::Eight),
             _ => None,
         }
     }
@@ -93,3 +139,125 @@ impl PdoSize {
         self as u8
     }
 }
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum AccessType {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+}
+
+impl AccessType {
+    /// `AccessType` in an EDS
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            AccessType::ReadOnly => "ro",
+            AccessType::WriteOnly => "wo",
+            AccessType::ReadWrite => "rw",
+        }
+    }
+}
+
+/// Taken from CiA 301, Table 44: Object dictionary data types
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[repr(u16)]
+pub enum DataType {
+    BOOLEAN = 0x1,
+    INTEGER8 = 0x2,
+    INTEGER16 = 0x3,
+    INTEGER32 = 0x4,
+    UNSIGNED8 = 0x5,
+    UNSIGNED16 = 0x6,
+    UNSIGNED32 = 0x7,
+    REAL32 = 0x8,
+    VISIBLE_STRING = 0x9,
+    OCTET_STRING = 0xA,
+    UNICODE_STRING = 0xB,
+    TIME_OF_DAY = 0xC,
+    TIME_DIFFERENCE = 0xD,
+    DOMAIN = 0xF,
+    INTEGER24 = 0x10,
+    REAL64 = 0x11,
+    INTEGER40 = 0x12,
+    INTEGER48 = 0x13,
+    INTEGER56 = 0x14,
+    INTEGER64 = 0x15,
+    UNSIGNED24 = 0x16,
+    UNSIGNED40 = 0x18,
+    UNSIGNED48 = 0x19,
+    UNSIGNED56 = 0x1A,
+    UNSIGNED64 = 0x1B,
+    PDO_COMMUNICATION_PARAMETER = 0x20,
+    PDO_MAPPING = 0x21,
+    SDO_PARAMETER = 0x22,
+    IDENTITY = 0x23,
+}
+
+impl DataType {
+    /// Index of the data type definition, `DataType` in an EDS
+    pub const fn code(self) -> u16 {
+        self as u16
+    }
+}
+
+/// Description of an object generated by the derive, e.g. for diagnostics
+#[derive(Clone, Copy, Debug)]
+pub struct ObjectMetadata {
+    /// `ParameterName` in an EDS
+    pub name: &'static str,
+    /// `None` if the derive couldn't tell the type of the field
+    pub data_type: Option<DataType>,
+}
+
+/// Range of values accepted on writes, `LowLimit` and `HighLimit` in an EDS
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Limits {
+    Signed { min: i64, max: i64 },
+    Unsigned { min: u64, max: u64 },
+    Real { min: f64, max: f64 },
+}
+
+impl Limits {
+    /// Check a little-endian encoded value.
+    ///
+    /// Values with a size that doesn't fit the limits are not checked.
+    pub fn check(&self, data: &[u8]) -> Result<(), ODError> {
+        let mut buf = [0; 8];
+        let Some(bytes) = buf.get_mut(..data.len()) else {
+            return Ok(());
+        };
+        bytes.copy_from_slice(data);
+        let shift = 64 - 8 * data.len() as u32;
+        match *self {
+            _ if data.is_empty() => Ok(()),
+            Limits::Signed { min, max } => {
+                // shifting back extends the sign
+                let value = (i64::from_le_bytes(buf) << shift) >> shift;
+                check_range(value, min, max)
+            }
+            Limits::Unsigned { min, max } => check_range(u64::from_le_bytes(buf), min, max),
+            Limits::Real { min, max } => {
+                let value = match data.len() {
+                    4 => f32::from_le_bytes(buf[..4].try_into().unwrap()) as f64,
+                    8 => f64::from_le_bytes(buf),
+                    _ => return Ok(()),
+                };
+                if value.is_nan() {
+                    return Err(ODError::InvalidValue);
+                }
+                check_range(value, min, max)
+            }
+        }
+    }
+}
+
+fn check_range<V: PartialOrd>(value: V, min: V, max: V) -> Result<(), ODError> {
+    if value < min {
+        Err(ODError::ValueTooLow)
+    } else if value > max {
+        Err(ODError::ValueTooHigh)
+    } else {
+        Ok(())
+    }
+}
@@ -1,6 +1,12 @@
+/// Wrapper for objects implementing [`CustomData`](crate::objectdictionary::datalink::CustomData)
+///
+/// Keeps track of segmented SDO transfers:
+/// * `locked` is set while a download is in progress, so the value is only partially written.
+/// * `read_locked` is set while an upload is in progress, so changing the value would tear it.
 pub struct OdCell<T> {
     pub value: T,
     pub locked: bool,
+    pub read_locked: bool,
 }
 
 impl<T> OdCell<T> {
@@ -8,19 +14,38 @@ impl<T> OdCell<T> {
         Self {
             value,
             locked: false,
+            read_locked: false,
         }
     }
     pub fn is_locked(&self) -> bool {
         self.locked
     }
-    // TODO docs about partial write going on
+    pub fn is_read_locked(&self) -> bool {
+        self.read_locked
+    }
     pub fn get(&self) -> &T {
         &self.value
     }
+    /// Mutable access, which takes precedence over SDO transfers.
+    ///
+    /// An ongoing download continues on the changed value,
+    /// an ongoing upload is aborted.
     pub fn get_mut(&mut self) -> &mut T {
         self.locked = false;
+        self.read_locked = false;
         &mut self.value
     }
+    /// Mutable access, unless an upload is in progress.
+    ///
+    /// Returns `None` while the value is read-locked. Try again later, or use
+    /// [`OdCell::get_mut`] to abort the upload.
+    pub fn try_get_mut(&mut self) -> Option<&mut T> {
+        if self.read_locked {
+            None
+        } else {
+            Some(self.get_mut())
+        }
+    }
     pub(crate) fn get_mut_unchecked(&mut self) -> &mut T {
         &mut self.value
     }
@@ -30,4 +55,10 @@ impl<T> OdCell<T> {
     pub(crate) fn unlock(&mut self) {
         self.locked = false;
     }
+    pub(crate) fn read_lock(&mut self) {
+        self.read_locked = true;
+    }
+    pub(crate) fn read_unlock(&mut self) {
+        self.read_locked = false;
+    }
 }
@@ -0,0 +1,105 @@
+//! Structs nested as RECORD objects
+//!
+//! ```
+//! use canopen::objectdictionary::OdData;
+//!
+//! #[derive(OdData)]
+//! #[canopen(record)]
+//! struct Identity {
+//!     #[canopen(read_only)]
+//!     vendor_id: u32,
+//!     #[canopen(read_only)]
+//!     product_code: u32,
+//! }
+//!
+//! #[derive(OdData)]
+//! struct Data {
+//!     #[canopen(record = "Identity", index = 0x1018)]
+//!     identity: Identity,
+//! }
+//!
+//! let mut od = Data {
+//!     identity: Identity {
+//!         vendor_id: 0x123,
+//!         product_code: 0x456,
+//!     },
+//! }
+//! .into_od();
+//! assert_eq!(od.get::<u8>(0x1018, 0), Ok(2));
+//! assert_eq!(od.get::<u32>(0x1018, 2), Ok(0x456));
+//! ```
+
+use crate::meta::DynMetadata;
+use crate::objectdictionary::datalink::DataLink;
+use crate::objectdictionary::object::{Limits, ObjectFlags, ObjectMetadata};
+use crate::objectdictionary::{ODError, OdPosition};
+
+/// Implemented by `#[derive(OdData)]` for structs with `#[canopen(record)]`
+///
+/// The fields become subindex 1 and above, numbered in the order of declaration
+/// unless they give a `subindex`. Subindex 0 is the struct itself, holding the highest subindex.
+/// All tables are sorted by subindex and refer to the fields by their position in it.
+///
+/// Structs with nested records can't be generic. Their `eds_path` can only be used if the
+/// records are derived earlier in the same crate, as only that derive knows their fields.
+pub trait OdRecord: DataLink {
+    /// Number of subindices, not counting subindex 0
+    const LEN: usize;
+    const SUBINDICES: &'static [u8];
+    const FLAGS: &'static [ObjectFlags];
+    /// Offsets of the fields inside the struct
+    const OFFSETS: &'static [usize];
+    const METADATA: &'static [ObjectMetadata];
+    /// Only fields with limits are listed
+    const LIMITS: &'static [(usize, Limits)];
+    /// Only fields with a default value are listed
+    const DEFAULTS: &'static [(usize, &'static [u8])];
+
+    fn vtable(&self, position: usize) -> DynMetadata<dyn DataLink>;
+
+    /// Runs the `validate` function of the field at `position`
+    fn validate(&self, position: usize, value: &[u8]) -> Result<(), ODError>;
+}
+
+/// Fixed-size table filled in a const context by the derive of structs with nested records
+#[doc(hidden)]
+pub struct Table<T: Copy, const N: usize> {
+    items: [T; N],
+    len: usize,
+}
+
+impl<T: Copy, const N: usize> Table<T, N> {
+    pub const fn new(fill: T) -> Self {
+        Table {
+            items: [fill; N],
+            len: 0,
+        }
+    }
+
+    pub const fn extend(&mut self, items: &[T]) {
+        let mut i = 0;
+        while i < items.len() {
+            self.items[self.len] = items[i];
+            self.len += 1;
+            i += 1;
+        }
+    }
+
+    /// Panics unless exactly `N` items were added
+    pub const fn finish(self) -> [T; N] {
+        assert!(self.len == N, "table size mismatch");
+        self.items
+    }
+}
+
+impl<V: Copy, const N: usize> Table<(OdPosition, V), N> {
+    /// Add the entries of a record starting at position `start`
+    pub const fn extend_record(&mut self, items: &[(usize, V)], start: usize) {
+        let mut i = 0;
+        while i < items.len() {
+            self.items[self.len] = (OdPosition(start + items[i].0), items[i].1);
+            self.len += 1;
+            i += 1;
+        }
+    }
+}
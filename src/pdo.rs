@@ -1,10 +1,11 @@
 use core::num::NonZeroU16;
+use core::time::Duration;
 
 use embedded_can::{ExtendedId, Id, StandardId};
 
-use crate::objectdictionary::datalink::{BasicData, BasicReadData, BasicWriteData};
+use crate::objectdictionary::datalink::{BasicData, BasicReadData, BasicWriteData, WriteData};
 use crate::objectdictionary::object::{ObjectInfo, PdoSize};
-use crate::objectdictionary::{ODError, OdInfo};
+use crate::objectdictionary::{ODError, OdInfo, OdPosition};
 use crate::sdo::SDOAbortCode;
 use crate::NodeId;
 use crate::ObjectDictionary;
@@ -14,95 +15,224 @@ pub struct TPDO {
     pub com: PDOCommunicationParameter,
     /// index 0x1A00 to 0x1BFF
     pub map: TPDOMappingParameters,
+    /// Mapping restored by a reset
+    power_on_map: TPDOMappingParameters,
+    last_transmission: Option<Duration>,
+    /// Start of the event timer, the last transmission or the first tick
+    timer_start: Option<Duration>,
+    sync_counter: u8,
+    /// Whether a SYNC with the counter value `sync_start_value` was received
+    sync_started: bool,
+    event: bool,
 }
 
 impl TPDO {
     #[inline]
     pub fn new(com: PDOCommunicationParameter, map: TPDOMappingParameters) -> Self {
-        TPDO { com, map }
+        TPDO {
+            com,
+            power_on_map: map.clone(),
+            map,
+            last_transmission: None,
+            timer_start: None,
+            sync_counter: 0,
+            sync_started: false,
+            event: false,
+        }
+    }
+
+    /// Restore the communication and mapping parameters to the values given on construction.
+    pub fn reset(&mut self) {
+        self.com.reset();
+        self.map = self.power_on_map.clone();
+        self.last_transmission = None;
+        self.timer_start = None;
+        self.sync_counter = 0;
+        self.sync_started = false;
+        self.event = false;
+    }
+
+    /// Move COB-IDs of the pre-defined connection set from `old_node_id` to `new_node_id`.
+    pub fn set_node_id(&mut self, old_node_id: NodeId, new_node_id: NodeId) {
+        self.com.set_node_id(old_node_id, new_node_id);
+    }
+
+    /// Request the transmission of an event-driven or synchronous acyclic TPDO.
+    pub fn trigger(&mut self) {
+        self.event = true;
     }
 
     pub fn create_frame<F: embedded_can::Frame, T, const N: usize>(
         &self,
         od: &mut ObjectDictionary<T, N>,
     ) -> Result<F, SDOAbortCode> {
-        let mut buf = [0; 8];
-        let mut frame_len = 0;
-        for i in 0..self.map.num_mapped_objects as usize {
-            if let Some(info) = &self.map.map[i] {
-                let data = od.get(info.od_position).read(info.index, info.subindex)?;
-                let bytes = data.as_bytes();
-                buf[frame_len..frame_len + bytes.len()].copy_from_slice(bytes);
-                frame_len += bytes.len();
+        self.map.create_frame(self.com.cob_id().id, od)
+    }
+
+    /// Whether the TPDO is due after receiving a SYNC message with the optional `counter`.
+    ///
+    /// Cyclic TPDOs with a `sync_start_value` only start counting with the SYNC
+    /// carrying that counter value, SYNCs without a counter start them right away.
+    pub(crate) fn on_sync(&mut self, counter: Option<u8>) -> bool {
+        if !self.is_active() {
+            return false;
+        }
+        match self.com.transmission_type {
+            0 => core::mem::take(&mut self.event),
+            n @ 1..=240 => {
+                if !self.sync_started {
+                    if self.com.sync_start_value != 0
+                        && counter.is_some_and(|counter| counter != self.com.sync_start_value)
+                    {
+                        return false;
+                    }
+                    self.sync_started = true;
+                    self.sync_counter = 0;
+                }
+                self.sync_counter += 1;
+                if self.sync_counter >= n {
+                    self.sync_counter = 0;
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether an event-driven TPDO is due.
+    ///
+    /// It is sent after [`TPDO::trigger`] or once the event timer expires,
+    /// which starts with the first tick the TPDO is active.
+    pub(crate) fn on_tick(&mut self, now: Duration) -> bool {
+        if !self.is_active() || self.com.transmission_type < 0xFE {
+            return false;
+        }
+        let timer_start = *self.timer_start.get_or_insert(now);
+        if let (Some(last_transmission), Some(inhibit_time)) =
+            (self.last_transmission, self.com.inhibit_time().us())
+        {
+            if now.saturating_sub(last_transmission) < Duration::from_micros(inhibit_time as u64) {
+                return false;
             }
         }
+        self.event
+            || (self.com.event_timer > 0
+                && now.saturating_sub(timer_start)
+                    >= Duration::from_millis(self.com.event_timer as u64))
+    }
+
+    pub(crate) fn on_transmission(&mut self, now: Duration) {
+        self.last_transmission = Some(now);
+        self.timer_start = Some(now);
+        self.event = false;
+    }
 
-        Ok(F::new(self.com.cob_id().id, &buf[0..frame_len]).unwrap())
+    fn is_active(&self) -> bool {
+        self.com.is_enabled() && self.map.num_mapped_objects > 0
     }
 }
 
 impl BasicData for TPDO {
     fn read(&mut self, index: u16, subindex: u8) -> Result<BasicReadData, ODError> {
         match index {
-            0x1800..=0x19FF => match subindex {
-                1 => Ok(self.com.cob_id.into()),
-                2 => Ok(self.com.transmission_type.into()),
-                3 => Ok(self.com.inhibit_time.into()),
-                5 => Ok(self.com.event_timer.into()),
-                6 => Ok(self.com.sync_start_value.into()),
-                _ => unreachable!(),
-            },
-            0x1A00..=0x1BFF => match subindex {
-                0 => Ok(self.map.num_mapped_objects.into()),
-                n => Ok(self.map.get_map_data_packed(n).into()),
-            },
+            0x1800..=0x19FF => Ok(self.com.read(subindex)),
+            0x1A00..=0x1BFF => Ok(self.map.read(subindex)),
             _ => unreachable!(),
         }
     }
 
     fn write(&mut self, data: BasicWriteData, od_info: OdInfo) -> Result<(), ODError> {
-        // if currently valid, the only allowed write is to the valid bit
-        if self.com.cob_id().valid && (data.index() > 0x19FF || data.subindex() != 1) {
-            return Err(ODError::DeviceStateError);
+        match data.index() {
+            0x1800..=0x19FF => self.com.write(data),
+            0x1A00..=0x1BFF => self.map.write(&self.com, data, od_info, false),
+            _ => unreachable!(),
         }
+    }
+}
 
-        match data.index() {
-            0x1800..=0x19FF => match data.subindex() {
-                1 => {
-                    let new_cob_id = u32::try_from(data)?;
-                    let new_cob_id =
-                        (self.com.cob_id_update_func)(self.com.cob_id(), CobId::from(new_cob_id))?;
+pub struct RPDO {
+    /// index 0x1400 to 0x15FF
+    pub com: PDOCommunicationParameter,
+    /// index 0x1600 to 0x17FF
+    pub map: RPDOMappingParameters,
+    /// Mapping restored by a reset
+    power_on_map: RPDOMappingParameters,
+    /// Data of a synchronous RPDO, written to the mapped objects on the next SYNC
+    received: Option<heapless::Vec<u8, 8>>,
+}
 
-                    self.com.cob_id = new_cob_id.into();
-                }
-                2 => self.com.transmission_type = data.try_into()?,
-                3 => self.com.inhibit_time = data.try_into()?,
-                5 => self.com.event_timer = data.try_into()?,
-                6 => self.com.sync_start_value = data.try_into()?,
-                _ => unreachable!(),
-            },
-            0x1A00..=0x1BFF => {
-                if data.subindex() == 0 {
-                    self.map.num_mapped_objects = data.try_into()?;
-                    return Ok(());
-                }
-                if self.map.num_mapped_objects > 0 {
-                    // num_mapped_objects needs to be set to 0 before updating mapping
-                    return Err(ODError::DeviceStateError);
-                }
-                let map_slot = data.subindex() as usize - 1;
-                if let Ok(data) = data.try_into() {
-                    let (index, subindex, num_bits) = unpack_object_data(data);
-
-                    return match od_info.find(index, subindex) {
-                        Some(info) => self.map.map_object(map_slot, info, num_bits),
-                        None => return Err(ODError::ObjectDoesNotExist),
-                    };
-                }
-            }
+impl RPDO {
+    #[inline]
+    pub fn new(com: PDOCommunicationParameter, map: RPDOMappingParameters) -> Self {
+        RPDO {
+            com,
+            power_on_map: map.clone(),
+            map,
+            received: None,
+        }
+    }
+
+    /// Restore the communication and mapping parameters to the values given on construction.
+    pub fn reset(&mut self) {
+        self.com.reset();
+        self.map = self.power_on_map.clone();
+        self.received = None;
+    }
+
+    /// Move COB-IDs of the pre-defined connection set from `old_node_id` to `new_node_id`.
+    pub fn set_node_id(&mut self, old_node_id: NodeId, new_node_id: NodeId) {
+        self.com.set_node_id(old_node_id, new_node_id);
+    }
+
+    /// Whether a frame with `id` belongs to this RPDO
+    pub(crate) fn accepts(&self, id: Id) -> bool {
+        self.is_active() && self.com.cob_id().id == id
+    }
+
+    /// The data to write to the mapped objects now.
+    ///
+    /// Data of synchronous RPDOs is kept until the next SYNC.
+    pub(crate) fn on_message(&mut self, data: &[u8]) -> Option<heapless::Vec<u8, 8>> {
+        let data = heapless::Vec::from_slice(data).ok()?;
+        if self.com.transmission_type <= 240 {
+            self.received = Some(data);
+            None
+        } else {
+            Some(data)
+        }
+    }
+
+    /// The data received before the SYNC, if any.
+    pub(crate) fn on_sync(&mut self) -> Option<heapless::Vec<u8, 8>> {
+        if !self.is_active() {
+            return None;
+        }
+        self.received.take()
+    }
+
+    fn is_active(&self) -> bool {
+        self.com.is_enabled() && self.map.num_mapped_objects > 0
+    }
+}
+
+impl BasicData for RPDO {
+    fn read(&mut self, index: u16, subindex: u8) -> Result<BasicReadData, ODError> {
+        match index {
+            0x1400..=0x15FF => Ok(self.com.read(subindex)),
+            0x1600..=0x17FF => Ok(self.map.read(subindex)),
+            _ => unreachable!(),
+        }
+    }
+
+    fn write(&mut self, data: BasicWriteData, od_info: OdInfo) -> Result<(), ODError> {
+        match data.index() {
+            0x1400..=0x15FF => self.com.write(data),
+            // received data can only be written to writable objects
+            0x1600..=0x17FF => self.map.write(&self.com, data, od_info, true),
             _ => unreachable!(),
         }
-        Ok(())
     }
 }
 
@@ -116,6 +246,13 @@ pub enum DefaultTPDO {
 }
 
 impl DefaultTPDO {
+    pub const ALL: [DefaultTPDO; 4] = [
+        DefaultTPDO::TPDO1,
+        DefaultTPDO::TPDO2,
+        DefaultTPDO::TPDO3,
+        DefaultTPDO::TPDO4,
+    ];
+
     #[allow(clippy::new_ret_no_self)]
     pub fn new(
         self,
@@ -139,6 +276,50 @@ impl DefaultTPDO {
     }
 }
 
+#[derive(Copy, Clone)]
+#[repr(u16)]
+pub enum DefaultRPDO {
+    RPDO1 = 0,
+    RPDO2 = 1,
+    RPDO3 = 2,
+    RPDO4 = 3,
+}
+
+impl DefaultRPDO {
+    pub const ALL: [DefaultRPDO; 4] = [
+        DefaultRPDO::RPDO1,
+        DefaultRPDO::RPDO2,
+        DefaultRPDO::RPDO3,
+        DefaultRPDO::RPDO4,
+    ];
+
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        self,
+        node_id: NodeId,
+        cob_id_update_func: fn(CobId, CobId) -> Result<CobId, InvalidCobId>,
+    ) -> RPDO {
+        RPDO::new(
+            PDOCommunicationParameter::new(self.cob_id(node_id, false), cob_id_update_func),
+            RPDOMappingParameters::default(),
+        )
+    }
+
+    pub fn cob_id(self, node_id: NodeId, valid: bool) -> CobId {
+        // SAFETY: Maximum StandardId is 0x7FF, maximum self is 3, maximum node_id is 0x7F
+        let id = unsafe {
+            Id::Standard(StandardId::new_unchecked(
+                0x200 + 0x100 * self as u16 + node_id.raw() as u16,
+            ))
+        };
+        CobId {
+            valid,
+            rtr: false,
+            id,
+        }
+    }
+}
+
 pub struct CobId {
     pub valid: bool,
     /// Only meaningful for TPDO
@@ -224,6 +405,7 @@ pub struct PDOCommunicationParameter {
     /// subindex 6
     sync_start_value: u8,
     cob_id_update_func: fn(CobId, CobId) -> Result<CobId, InvalidCobId>,
+    power_on_cob_id: u32,
 }
 
 impl PDOCommunicationParameter {
@@ -231,26 +413,100 @@ impl PDOCommunicationParameter {
         cob_id: CobId,
         cob_id_update_func: fn(CobId, CobId) -> Result<CobId, InvalidCobId>,
     ) -> Self {
+        let cob_id = cob_id.into();
         PDOCommunicationParameter {
-            cob_id: cob_id.into(),
+            cob_id,
             transmission_type: 0,
             inhibit_time: 0,
             event_timer: 0,
             sync_start_value: 0,
             cob_id_update_func,
+            power_on_cob_id: cob_id,
         }
     }
 
+    /// Restore the values given on construction.
+    pub fn reset(&mut self) {
+        self.cob_id = self.power_on_cob_id;
+        self.transmission_type = 0;
+        self.inhibit_time = 0;
+        self.event_timer = 0;
+        self.sync_start_value = 0;
+    }
+
+    /// Move COB-IDs of the pre-defined connection set from `old_node_id` to `new_node_id`.
+    ///
+    /// COB-IDs that were configured independently of the node-ID are kept.
+    pub fn set_node_id(&mut self, old_node_id: NodeId, new_node_id: NodeId) {
+        let update = |cob_id: u32| {
+            let CobId { valid, rtr, id } = CobId::from(cob_id);
+            for default_tpdo in DefaultTPDO::ALL {
+                if id == default_tpdo.cob_id(old_node_id, valid, rtr).id {
+                    return default_tpdo.cob_id(new_node_id, valid, rtr).into();
+                }
+            }
+            for default_rpdo in DefaultRPDO::ALL {
+                if id == default_rpdo.cob_id(old_node_id, valid).id {
+                    return default_rpdo.cob_id(new_node_id, valid).into();
+                }
+            }
+            cob_id
+        };
+        self.cob_id = update(self.cob_id);
+        self.power_on_cob_id = update(self.power_on_cob_id);
+    }
+
     pub fn cob_id(&self) -> CobId {
         self.cob_id.into()
     }
 
+    fn read(&self, subindex: u8) -> BasicReadData {
+        match subindex {
+            1 => self.cob_id.into(),
+            2 => self.transmission_type.into(),
+            3 => self.inhibit_time.into(),
+            5 => self.event_timer.into(),
+            6 => self.sync_start_value.into(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn write(&mut self, data: BasicWriteData) -> Result<(), ODError> {
+        // if currently valid, the only allowed write is to the valid bit
+        if self.cob_id().valid && data.subindex() != 1 {
+            return Err(ODError::DeviceStateError);
+        }
+
+        match data.subindex() {
+            1 => {
+                let new_cob_id = u32::try_from(data)?;
+                let new_cob_id = (self.cob_id_update_func)(self.cob_id(), CobId::from(new_cob_id))?;
+
+                self.cob_id = new_cob_id.into();
+            }
+            2 => self.transmission_type = data.try_into()?,
+            3 => self.inhibit_time = data.try_into()?,
+            5 => self.event_timer = data.try_into()?,
+            6 => self.sync_start_value = data.try_into()?,
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
     pub fn inhibit_time(&self) -> InhibitTime {
         self.inhibit_time.into()
     }
+
+    /// CiA 301: the PDO exists if bit 31 of the COB-ID is cleared
+    pub fn is_enabled(&self) -> bool {
+        self.cob_id & (1 << 31) == 0
+    }
 }
 
-#[derive(Default)]
+/// The mapping of an RPDO has the same layout as the one of a TPDO
+pub type RPDOMappingParameters = TPDOMappingParameters;
+
+#[derive(Clone, Default)]
 pub struct TPDOMappingParameters {
     /// The number of valid object entries within the mapping record.
     /// The number of valid object entries shall be the number of the application objects
@@ -260,6 +516,73 @@ pub struct TPDOMappingParameters {
 }
 
 impl TPDOMappingParameters {
+    pub fn create_frame<F: embedded_can::Frame, T, const N: usize>(
+        &self,
+        id: Id,
+        od: &mut ObjectDictionary<T, N>,
+    ) -> Result<F, SDOAbortCode> {
+        let mut buf = [0; 8];
+        let mut frame_len = 0;
+        for i in 0..self.num_mapped_objects as usize {
+            if let Some(info) = &self.map[i] {
+                let data = od.link(info.od_position).read(info.index, info.subindex)?;
+                let bytes = data.as_bytes();
+                buf[frame_len..frame_len + bytes.len()].copy_from_slice(bytes);
+                frame_len += bytes.len();
+            }
+        }
+
+        Ok(F::new(id, &buf[0..frame_len]).unwrap())
+    }
+
+    /// Write the data of a received PDO to the mapped objects.
+    ///
+    /// Fails with [`ODError::TooShort`] without writing anything
+    /// if `data` doesn't cover all mapped objects, surplus bytes are ignored.
+    /// An object refusing its value doesn't stop the others from being written.
+    pub fn write_frame<T, const N: usize>(
+        &self,
+        data: &[u8],
+        od: &mut ObjectDictionary<T, N>,
+    ) -> Result<(), ODError> {
+        if self
+            .mapped_bytes(self.num_mapped_objects)
+            .is_none_or(|bytes| bytes > data.len())
+        {
+            return Err(ODError::TooShort);
+        }
+        let mut result = Ok(());
+        let mut offset = 0;
+        for info in self.map[..self.num_mapped_objects as usize]
+            .iter()
+            .flatten()
+        {
+            let Some(size) = info.flags.pdo_size() else {
+                continue;
+            };
+            let new_data = &data[offset..offset + size.get() as usize];
+            offset += new_data.len();
+            let data = WriteData {
+                index: info.index,
+                subindex: info.subindex,
+                new_data,
+                offset: 0,
+                promised_size: Some(new_data.len()),
+                is_last_segment: true,
+            };
+            result = result.and(od.write_position(info.od_position, &data));
+        }
+        result
+    }
+
+    /// Whether the object at `position` is mapped into the PDO
+    pub fn contains(&self, position: OdPosition) -> bool {
+        self.map[..self.num_mapped_objects as usize]
+            .iter()
+            .flatten()
+            .any(|info| info.od_position == position)
+    }
+
     // slot 1-8
     pub fn map_object(
         &mut self,
@@ -268,7 +591,7 @@ impl TPDOMappingParameters {
         num_bits: u8,
     ) -> Result<(), ODError> {
         // validate num_bits
-        if num_bits % 8 != 0 || PdoSize::new(num_bits / 8) != info.flags.pdo_size() {
+        if !num_bits.is_multiple_of(8) || PdoSize::new(num_bits / 8) != info.flags.pdo_size() {
             return Err(ODError::ObjectCannotBeMapped);
         }
         match info.flags.pdo_size() {
@@ -281,6 +604,74 @@ impl TPDOMappingParameters {
         }
     }
 
+    fn read(&self, subindex: u8) -> BasicReadData {
+        match subindex {
+            0 => self.num_mapped_objects.into(),
+            n => self.get_map_data_packed(n).into(),
+        }
+    }
+
+    /// Write subindex 0 or a mapping entry, `is_rpdo` only allows writable objects.
+    fn write(
+        &mut self,
+        com: &PDOCommunicationParameter,
+        data: BasicWriteData,
+        od_info: OdInfo,
+        is_rpdo: bool,
+    ) -> Result<(), ODError> {
+        // if currently valid, the only allowed write is to the valid bit
+        if com.cob_id().valid {
+            return Err(ODError::DeviceStateError);
+        }
+        if data.subindex() == 0 {
+            return self.set_num_mapped_objects(data.try_into()?);
+        }
+        if self.num_mapped_objects > 0 {
+            // num_mapped_objects needs to be set to 0 before updating mapping
+            return Err(ODError::DeviceStateError);
+        }
+        let map_slot = data.subindex() as usize - 1;
+        if let Ok(data) = data.try_into() {
+            let (index, subindex, num_bits) = unpack_object_data(data);
+
+            return match od_info.find(index, subindex) {
+                Some(info) if is_rpdo && info.flags.is_read_only() => {
+                    Err(ODError::ObjectCannotBeMapped)
+                }
+                Some(info) => self.map_object(map_slot, info, num_bits),
+                None => Err(ODError::ObjectDoesNotExist),
+            };
+        }
+        Ok(())
+    }
+
+    /// Enable the objects in the first `num_mapped_objects` slots, 0 disables the PDO
+    pub fn set_num_mapped_objects(&mut self, num_mapped_objects: u8) -> Result<(), ODError> {
+        if self
+            .mapped_bytes(num_mapped_objects)
+            .is_none_or(|bytes| bytes > 8)
+        {
+            return Err(ODError::PDOOverflow);
+        }
+        self.num_mapped_objects = num_mapped_objects;
+        Ok(())
+    }
+
+    /// Length of the first `num_mapped_objects` objects in bytes
+    ///
+    /// `None` if there are not as many slots.
+    fn mapped_bytes(&self, num_mapped_objects: u8) -> Option<usize> {
+        let mapped = self.map.get(..num_mapped_objects as usize)?;
+        Some(
+            mapped
+                .iter()
+                .flatten()
+                .filter_map(|info| info.flags.pdo_size())
+                .map(|size| size.get() as usize)
+                .sum(),
+        )
+    }
+
     #[inline]
     pub fn get_map_data_packed(&self, num: u8) -> u32 {
         match &self.map[num as usize - 1] {
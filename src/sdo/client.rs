@@ -3,6 +3,7 @@ use core::fmt::Debug;
 
 use embedded_can::{Frame, StandardId};
 
+use crate::objectdictionary::integer::{I24, I40, I48, I56, U24, U40, U48, U56};
 use crate::{NodeId, SdoMessage};
 
 use super::*;
@@ -30,7 +31,7 @@ impl SdoClient {
         }
     }
 
-    pub fn read(&self, index: u16, subindex: u8) -> Reader {
+    pub fn read(&self, index: u16, subindex: u8) -> Reader<'_> {
         // clear SDO buffer
         self.buffer.dequeue();
         self.buffer.dequeue();
@@ -40,6 +41,20 @@ impl SdoClient {
         }
     }
 
+    /// Download `data` using an expedited or segmented transfer.
+    pub fn write<B: AsRef<[u8]>>(&self, index: u16, subindex: u8, data: B) -> Writer<'_, B> {
+        // clear SDO buffer
+        self.buffer.dequeue();
+        self.buffer.dequeue();
+        Writer {
+            sdo_client: self,
+            index,
+            subindex,
+            data,
+            state: WriterState::Init,
+        }
+    }
+
     pub fn upload_request(&self, index: u16, sub_index: u8) -> SdoMessage {
         let mut request = [0; 8];
         request[0] = REQUEST_UPLOAD;
@@ -74,9 +89,19 @@ pub struct Reader<'a> {
 }
 
 enum ReaderState {
-    Init { index: u16, subindex: u8 },
-    RequestSent { index: u16, subindex: u8 },
-    Segmented { toggle_bit: bool },
+    Init {
+        index: u16,
+        subindex: u8,
+    },
+    RequestSent {
+        index: u16,
+        subindex: u8,
+    },
+    Segmented {
+        toggle_bit: bool,
+        /// Small values are collected and read at once
+        buffer: Option<heapless::Vec<u8, 8>>,
+    },
     Done,
 }
 
@@ -125,8 +150,14 @@ impl Reader<'_> {
             buf.read_into(data)?;
             Ok(ReadResult::Done)
         } else {
-            self.state = ReaderState::Segmented { toggle_bit: false };
-            // FIXME maybe use size in last 4 bytes
+            let size = match response[0] & SIZE_SPECIFIED {
+                0 => None,
+                _ => Some(u32::from_le_bytes(response[4..8].try_into().unwrap())),
+            };
+            self.state = ReaderState::Segmented {
+                toggle_bit: false,
+                buffer: size.is_some_and(|size| size <= 8).then(heapless::Vec::new),
+            };
             let message = self.sdo_client.segmented_upload_request(false);
             Ok(ReadResult::NextRequest(message))
         }
@@ -137,7 +168,7 @@ impl Reader<'_> {
         response: [u8; 8],
         buf: &mut B,
     ) -> Result<ReadResult<SdoMessage>, ProtocolError> {
-        let ReaderState::Segmented { toggle_bit } = &mut self.state else {
+        let ReaderState::Segmented { toggle_bit, buffer } = &mut self.state else {
             return Err(ProtocolError::ParseError);
         };
         if (response[0] & TOGGLE_BIT > 0) != *toggle_bit {
@@ -145,8 +176,19 @@ impl Reader<'_> {
         }
         let n = (response[0] >> 1) & 0x7;
         let data = &response[1..8 - n as usize];
-        buf.read_into(data)?;
-        if response[0] & NO_MORE_DATA > 0 {
+        let no_more_data = response[0] & NO_MORE_DATA > 0;
+        match buffer {
+            Some(buffer) => {
+                buffer
+                    .extend_from_slice(data)
+                    .map_err(|_| ProtocolError::ParseError)?;
+                if no_more_data {
+                    buf.read_into(buffer)?;
+                }
+            }
+            None => buf.read_into(data)?,
+        }
+        if no_more_data {
             self.state = ReaderState::Done;
             Ok(ReadResult::Done)
         } else {
@@ -158,15 +200,126 @@ impl Reader<'_> {
     }
 }
 
+pub struct Writer<'a, B> {
+    sdo_client: &'a SdoClient,
+    index: u16,
+    subindex: u8,
+    data: B,
+    state: WriterState,
+}
+
+enum WriterState {
+    Init,
+    RequestSent,
+    Segmented {
+        toggle_bit: bool,
+        bytes_downloaded: usize,
+    },
+    Done,
+}
+
+impl<B: AsRef<[u8]>> Writer<'_, B> {
+    pub fn poll(&mut self) -> Result<ReadResult<SdoMessage>, ProtocolError> {
+        let data = self.data.as_ref();
+        match self.state {
+            WriterState::Init => {
+                let request = if is_expedited(data) {
+                    expedited_download_request(self.index, self.subindex, data)
+                } else {
+                    let mut request = [REQUEST_DOWNLOAD | SIZE_SPECIFIED, 0, 0, 0, 0, 0, 0, 0];
+                    request[1..3].copy_from_slice(&self.index.to_le_bytes());
+                    request[3] = self.subindex;
+                    request[4..8].copy_from_slice(&(data.len() as u32).to_le_bytes());
+                    request
+                };
+                self.state = WriterState::RequestSent;
+                Ok(ReadResult::NextRequest(self.sdo_client.message(request)))
+            }
+            WriterState::Done => Ok(ReadResult::Done),
+            _ => {
+                let Some(response) = self.sdo_client.buffer.dequeue() else {
+                    return Ok(ReadResult::Waiting);
+                };
+                match (response_css(&response), &self.state) {
+                    (RESPONSE_ABORTED, _) => Err(to_abort_code(&response).into()),
+                    (RESPONSE_DOWNLOAD, WriterState::RequestSent) => {
+                        check_response_index(&response, self.index, self.subindex)?;
+                        if is_expedited(data) {
+                            self.state = WriterState::Done;
+                            Ok(ReadResult::Done)
+                        } else {
+                            Ok(self.next_segment(false, 0))
+                        }
+                    }
+                    (
+                        RESPONSE_SEGMENT_DOWNLOAD,
+                        &WriterState::Segmented {
+                            toggle_bit,
+                            bytes_downloaded,
+                        },
+                    ) => {
+                        if (response[0] & TOGGLE_BIT > 0) != toggle_bit {
+                            return Err(ProtocolError::ParseError);
+                        }
+                        if bytes_downloaded == data.len() {
+                            self.state = WriterState::Done;
+                            Ok(ReadResult::Done)
+                        } else {
+                            Ok(self.next_segment(!toggle_bit, bytes_downloaded))
+                        }
+                    }
+                    _ => Err(ProtocolError::ParseError),
+                }
+            }
+        }
+    }
+
+    fn next_segment(&mut self, toggle_bit: bool, offset: usize) -> ReadResult<SdoMessage> {
+        let unsent_data = &self.data.as_ref()[offset..];
+        let size = unsent_data.len().min(7);
+
+        let mut request = [REQUEST_SEGMENT_DOWNLOAD, 0, 0, 0, 0, 0, 0, 0];
+        if toggle_bit {
+            request[0] |= TOGGLE_BIT;
+        }
+        request[0] |= (7 - size as u8) << 1; // number of bytes not used
+        if unsent_data.len() <= 7 {
+            request[0] |= NO_MORE_DATA;
+        }
+        request[1..size + 1].copy_from_slice(&unsent_data[..size]);
+
+        self.state = WriterState::Segmented {
+            toggle_bit,
+            bytes_downloaded: offset + size,
+        };
+        ReadResult::NextRequest(self.sdo_client.message(request))
+    }
+}
+
 pub enum ReadResult<F> {
     NextRequest(F),
     Waiting,
     Done,
 }
 
-pub fn download_request<T: SdoValue>(index: u16, sub_index: u8, val: T) -> [u8; 8] {
-    let bytes = val.to_bytes();
-    let data = bytes.as_ref();
+/// Expedited download request
+///
+/// Larger values like `u64` need a segmented transfer, use [`SdoClient::write`] for them.
+///
+/// ```compile_fail
+/// # use canopen::sdo::client::download_request;
+/// download_request(0x2000, 0, 1u64);
+/// ```
+pub fn download_request<T: ExpeditedSdoValue>(index: u16, sub_index: u8, val: T) -> [u8; 8] {
+    expedited_download_request(index, sub_index, val.to_bytes().as_ref())
+}
+
+/// Empty data can't be sent expedited, as its size can't be indicated
+fn is_expedited(data: &[u8]) -> bool {
+    (1..=4).contains(&data.len())
+}
+
+fn expedited_download_request(index: u16, sub_index: u8, data: &[u8]) -> [u8; 8] {
     let mut request = [0; 8];
     request[0] = REQUEST_DOWNLOAD | EXPEDITED | SIZE_SPECIFIED | ((4 - data.len()) << 2) as u8;
     request[1] = index as u8;
@@ -277,8 +430,11 @@ pub trait SdoValue: Sized {
 
 macro_rules! sdo_value {
     ($typ:ty) => {
+        sdo_value!($typ, core::mem::size_of::<$typ>());
+    };
+    ($typ:ty, $size:expr) => {
         impl SdoValue for $typ {
-            type Bytes = [u8; core::mem::size_of::<Self>()];
+            type Bytes = [u8; $size];
             fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
                 let bytes: Self::Bytes = bytes.try_into()?;
                 Ok(Self::from_le_bytes(bytes))
@@ -298,6 +454,31 @@ sdo_value!(i8);
 sdo_value!(i16);
 sdo_value!(i32);
 sdo_value!(f32);
+sdo_value!(u64);
+sdo_value!(i64);
+sdo_value!(f64);
+sdo_value!(U24, 3);
+sdo_value!(I24, 3);
+sdo_value!(U40, 5);
+sdo_value!(I40, 5);
+sdo_value!(U48, 6);
+sdo_value!(I48, 6);
+sdo_value!(U56, 7);
+sdo_value!(I56, 7);
+
+/// [`SdoValue`] of at most 4 bytes, which fits into an expedited transfer
+pub trait ExpeditedSdoValue: SdoValue {}
+
+impl ExpeditedSdoValue for u8 {}
+impl ExpeditedSdoValue for u16 {}
+impl ExpeditedSdoValue for u32 {}
+impl ExpeditedSdoValue for i8 {}
+impl ExpeditedSdoValue for i16 {}
+impl ExpeditedSdoValue for i32 {}
+impl ExpeditedSdoValue for f32 {}
+impl ExpeditedSdoValue for U24 {}
+impl ExpeditedSdoValue for I24 {}
+impl ExpeditedSdoValue for bool {}
 
 impl SdoValue for bool {
     type Bytes = [u8; 1];
@@ -331,9 +512,12 @@ impl ReadInto for Vec<u8> {
 
 macro_rules! read_into {
     ($typ:ty) => {
+        read_into!($typ, core::mem::size_of::<$typ>());
+    };
+    ($typ:ty, $size:expr) => {
         impl ReadInto for $typ {
             fn read_into(&mut self, buf: &[u8]) -> Result<(), ParseError> {
-                let bytes: [u8; core::mem::size_of::<Self>()] = buf.try_into()?;
+                let bytes: [u8; $size] = buf.try_into()?;
                 *self = Self::from_le_bytes(bytes);
                 Ok(())
             }
@@ -348,6 +532,17 @@ read_into!(i8);
 read_into!(i16);
 read_into!(i32);
 read_into!(f32);
+read_into!(u64);
+read_into!(i64);
+read_into!(f64);
+read_into!(U24, 3);
+read_into!(I24, 3);
+read_into!(U40, 5);
+read_into!(I40, 5);
+read_into!(U48, 6);
+read_into!(I48, 6);
+read_into!(U56, 7);
+read_into!(I56, 7);
 
 impl ReadInto for bool {
     fn read_into(&mut self, bytes: &[u8]) -> Result<(), ParseError> {
@@ -1,3 +1,5 @@
+use core::time::Duration;
+
 use embedded_can::{Id, StandardId};
 
 use crate::objectdictionary::datalink::WriteData;
@@ -14,11 +16,15 @@ enum State {
         toggle_bit: u8,
         od_position: OdPosition,
         bytes_downloaded: usize,
+        /// Small values are written at once after the last segment
+        buffer: Option<[u8; 8]>,
     },
     SegmentedUpload {
         toggle_bit: u8,
         od_position: OdPosition,
         bytes_uploaded: usize,
+        /// `None` if the end is only known when reaching it
+        size: Option<usize>,
     },
 }
 
@@ -28,6 +34,10 @@ pub struct SdoServer {
     last_index: u16,
     last_subindex: u8,
     state: State,
+    /// Time without a request after which an ongoing transfer is aborted
+    pub timeout: Duration,
+    /// Start of the wait for the next request of an ongoing transfer
+    idle_since: Option<Duration>,
 }
 
 impl SdoServer {
@@ -38,7 +48,54 @@ impl SdoServer {
             last_index: 0,
             last_subindex: 0,
             state: State::None,
+            timeout: Duration::from_secs(1),
+            idle_since: None,
+        }
+    }
+
+    /// Use the default SDO COB-IDs of another node-ID
+    pub fn set_node_id(&mut self, node_id: NodeId) {
+        self.rx_cobid = node_id.sdo_rx_cobid();
+        self.tx_cobid = node_id.sdo_tx_cobid();
+    }
+
+    /// Abort any ongoing transfer without sending a response.
+    ///
+    /// Has to be called when the communication is reset
+    /// or the SDO service becomes unavailable because of the NMT state.
+    pub fn reset(&mut self) {
+        self.state = State::None;
+    }
+
+    /// Like [`SdoServer::reset`], but also releases the object of an ongoing transfer,
+    /// e.g. the read lock of an [`OdCell`](crate::objectdictionary::od_cell::OdCell).
+    pub fn abort_transfer<T, const N: usize>(&mut self, od: &mut ObjectDictionary<T, N>) {
+        if let State::SegmentedDownload { od_position, .. }
+        | State::SegmentedUpload { od_position, .. } = self.state
+        {
+            od.link(od_position).abort_transfer();
+        }
+        self.state = State::None;
+    }
+
+    /// Abort a transfer the client has not continued within [`SdoServer::timeout`].
+    ///
+    /// Returns the abort message to be sent.
+    pub fn on_tick<T, const N: usize>(
+        &mut self,
+        now: Duration,
+        od: &mut ObjectDictionary<T, N>,
+    ) -> Option<SdoMessage> {
+        if matches!(self.state, State::None) {
+            self.idle_since = None;
+            return None;
+        }
+        let idle_since = *self.idle_since.get_or_insert(now);
+        if now.saturating_sub(idle_since) < self.timeout {
+            return None;
         }
+        self.abort_transfer(od);
+        Some(self.abort(SDOAbortCode::SDOProtocolTimedOut))
     }
 
     pub fn on_message<F: embedded_can::Frame, T, const N: usize>(
@@ -61,23 +118,24 @@ impl SdoServer {
         data: &[u8; 8],
         od: &mut ObjectDictionary<T, N>,
     ) -> Option<SdoMessage> {
+        self.idle_since = None;
         let ccs = data[0] & 0xE0;
 
         let result = match ccs {
             REQUEST_DOWNLOAD => {
-                self.state = State::None;
+                self.abort_transfer(od);
                 self.set_index(data);
                 self.init_download(data, od)
             }
             REQUEST_SEGMENT_DOWNLOAD => self.segmented_download(data, od),
             REQUEST_UPLOAD => {
-                self.state = State::None;
+                self.abort_transfer(od);
                 self.set_index(data);
                 self.init_upload(data, od)
             }
             REQUEST_SEGMENT_UPLOAD => self.segmented_upload(data[0], od),
             REQUEST_ABORTED => {
-                self.state = State::None;
+                self.abort_transfer(od);
                 Ok(None)
             }
             _ => Err(SDOAbortCode::CommandSpecifierError),
@@ -86,7 +144,7 @@ impl SdoServer {
             Ok(None) => None,
             Ok(Some(response)) => Some(SdoMessage::new(self.tx_cobid, response)),
             Err(abort_code) => {
-                self.state = State::None;
+                self.abort_transfer(od);
                 Some(self.abort(abort_code))
             }
         }
@@ -103,8 +161,7 @@ impl SdoServer {
         od: &mut ObjectDictionary<T, N>,
     ) -> RequestResult {
         let od_position = od.search(self.last_index, self.last_subindex)?;
-        let (link, info) = od.get_plus(od_position);
-        if info.get(od_position).flags.is_read_only() {
+        if od.flags(od_position).is_read_only() {
             return Err(SDOAbortCode::ReadOnlyError);
         }
 
@@ -113,8 +170,16 @@ impl SdoServer {
         stream.index = self.last_index;
         stream.subindex = self.last_subindex;
 
+        // basic data types can't handle partial writes, so their values are buffered,
+        // with or without a size indication
+        let is_buffered = !stream.is_last_segment
+            && (stream.promised_size.is_some_and(|size| size <= 8)
+                || od.link(od_position).is_basic());
+
         // write data
-        link.write(&stream, info)?; // TODO write even if no data?
+        if !is_buffered {
+            od.write_position(od_position, &stream)?; // TODO write even if no data?
+        }
 
         // update state
         if !stream.is_last_segment {
@@ -122,6 +187,7 @@ impl SdoServer {
                 toggle_bit: 0,
                 bytes_downloaded: 0,
                 od_position,
+                buffer: is_buffered.then_some([0; 8]),
             };
         }
 
@@ -142,6 +208,7 @@ impl SdoServer {
                 toggle_bit,
                 od_position,
                 bytes_downloaded,
+                buffer,
             } => {
                 // unpack command
                 let command = request[0];
@@ -151,18 +218,37 @@ impl SdoServer {
                 let last_byte = (8 - ((command >> 1) & 0x7)) as usize;
                 let no_more_data = command & NO_MORE_DATA != 0;
 
-                let stream = WriteData {
-                    index: self.last_index,
-                    subindex: self.last_subindex,
-                    promised_size: None,
-                    new_data: &request[1..last_byte],
-                    offset: *bytes_downloaded,
-                    is_last_segment: no_more_data,
-                };
+                let new_data = &request[1..last_byte];
 
                 // write data
-                let (link, info) = od.get_plus(*od_position);
-                link.write(&stream, info)?;
+                if let Some(buffer) = buffer {
+                    let bytes_buffered = *bytes_downloaded + new_data.len();
+                    buffer
+                        .get_mut(*bytes_downloaded..bytes_buffered)
+                        .ok_or(SDOAbortCode::TooLong)?
+                        .copy_from_slice(new_data);
+                    if no_more_data {
+                        let stream = WriteData {
+                            index: self.last_index,
+                            subindex: self.last_subindex,
+                            promised_size: Some(bytes_buffered),
+                            new_data: &buffer[..bytes_buffered],
+                            offset: 0,
+                            is_last_segment: true,
+                        };
+                        od.write_position(*od_position, &stream)?;
+                    }
+                } else {
+                    let stream = WriteData {
+                        index: self.last_index,
+                        subindex: self.last_subindex,
+                        promised_size: None,
+                        new_data,
+                        offset: *bytes_downloaded,
+                        is_last_segment: no_more_data,
+                    };
+                    od.write_position(*od_position, &stream)?;
+                }
 
                 // respond
                 let response = [RESPONSE_SEGMENT_DOWNLOAD | *toggle_bit, 0, 0, 0, 0, 0, 0, 0];
@@ -182,25 +268,44 @@ impl SdoServer {
         od: &mut ObjectDictionary<T, N>,
     ) -> RequestResult {
         let od_position = od.search(self.last_index, self.last_subindex)?;
-        let (link, info) = od.get_plus(od_position);
-        if info.get(od_position).flags.is_write_only() {
+        if od.flags(od_position).is_write_only() {
             return Err(SDOAbortCode::WriteOnlyError);
         }
+        let link = od.link(od_position);
 
-        let mut response = [RESPONSE_UPLOAD | SIZE_SPECIFIED, 0, 0, 0, 0, 0, 0, 0];
+        let mut response = [RESPONSE_UPLOAD, 0, 0, 0, 0, 0, 0, 0];
         response[1..4].copy_from_slice(&request[1..4]);
 
-        let is_expedited = fill_upload_response(
-            link.read(self.last_index, self.last_subindex)?.as_bytes(),
-            &mut response,
-        );
-
-        if !is_expedited {
-            self.state = State::SegmentedUpload {
-                toggle_bit: 0,
-                bytes_uploaded: 0,
-                od_position,
-            };
+        // the value must not change between taking its size and reading it
+        link.begin_read();
+        let size = link
+            .size(self.last_index, self.last_subindex)
+            .inspect_err(|_| link.abort_transfer())?;
+        match size {
+            // empty data can't be uploaded expedited, as its size can't be indicated
+            Some(size @ 1..=4) => {
+                let size = link
+                    .read_chunk(
+                        self.last_index,
+                        self.last_subindex,
+                        0,
+                        &mut response[4..4 + size],
+                    )
+                    .inspect_err(|_| link.abort_transfer())?;
+                response[0] |= SIZE_SPECIFIED | EXPEDITED | (4 - size as u8) << 2;
+            }
+            _ => {
+                if let Some(size) = size {
+                    response[0] |= SIZE_SPECIFIED;
+                    response[4..].copy_from_slice(&(size as u32).to_le_bytes());
+                }
+                self.state = State::SegmentedUpload {
+                    toggle_bit: 0,
+                    bytes_uploaded: 0,
+                    size,
+                    od_position,
+                };
+            }
         }
 
         Ok(Some(response))
@@ -215,6 +320,7 @@ impl SdoServer {
             State::SegmentedUpload {
                 toggle_bit,
                 bytes_uploaded,
+                size,
                 od_position,
             } => {
                 if command & TOGGLE_BIT != *toggle_bit {
@@ -224,13 +330,19 @@ impl SdoServer {
                 let mut response = [RESPONSE_SEGMENT_UPLOAD | *toggle_bit, 0, 0, 0, 0, 0, 0, 0];
                 *toggle_bit ^= TOGGLE_BIT;
 
-                let no_more_data = fill_segmented_upload_response(
-                    od.get(*od_position)
-                        .read(self.last_index, self.last_subindex)?
-                        .as_bytes(),
-                    &mut response,
-                    bytes_uploaded,
-                );
+                let segment_size = od.link(*od_position).read_chunk(
+                    self.last_index,
+                    self.last_subindex,
+                    *bytes_uploaded,
+                    &mut response[1..],
+                )?;
+                *bytes_uploaded += segment_size;
+                response[0] |= (7 - segment_size as u8) << 1; // add number of bytes not used
+
+                let no_more_data = segment_size < 7 || Some(*bytes_uploaded) == *size;
+                if no_more_data {
+                    response[0] |= NO_MORE_DATA;
+                }
                 if no_more_data {
                     self.state = State::None;
                 }
@@ -241,7 +353,8 @@ impl SdoServer {
         }
     }
 
-    fn abort(&mut self, abort_error: SDOAbortCode) -> SdoMessage {
+    /// Abort message for the last requested object
+    pub(crate) fn abort(&mut self, abort_error: SDOAbortCode) -> SdoMessage {
         let [index_lo, index_hi] = self.last_index.to_le_bytes();
         let subindex = self.last_subindex;
         let code: [u8; 4] = (abort_error as u32).to_le_bytes();
@@ -260,7 +373,7 @@ impl SdoServer {
     }
 }
 
-fn unpack_init_download_request(request: &[u8; 8]) -> WriteData {
+fn unpack_init_download_request(request: &[u8; 8]) -> WriteData<'_> {
     let mut stream = WriteData {
         index: 0,
         subindex: 0,
@@ -286,33 +399,3 @@ fn unpack_init_download_request(request: &[u8; 8]) -> WriteData {
 
     stream
 }
-fn fill_upload_response(data: &[u8], response: &mut [u8]) -> bool {
-    if data.len() <= 4 {
-        response[0] |= SIZE_SPECIFIED | EXPEDITED | (4 - data.len() as u8) << 2;
-        response[4..4 + data.len()].copy_from_slice(data);
-        true
-    } else {
-        response[4..].copy_from_slice(&(data.len() as u32).to_le_bytes());
-        false
-    }
-}
-fn fill_segmented_upload_response(
-    data: &[u8],
-    response: &mut [u8],
-    bytes_uploaded: &mut usize,
-) -> bool {
-    let unread_data = &data[*bytes_uploaded..];
-
-    let size = unread_data.len().min(7);
-
-    response[1..size + 1].copy_from_slice(&unread_data[..size]);
-    *bytes_uploaded += size;
-
-    response[0] |= (7 - size as u8) << 1; // add number of bytes not used
-    if unread_data.len() <= 7 {
-        response[0] |= NO_MORE_DATA; // nothing left in buffer
-        true
-    } else {
-        false
-    }
-}
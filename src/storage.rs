@@ -0,0 +1,403 @@
+//! Store parameters (object 0x1010) and restore default parameters (object 0x1011)
+//!
+//! Objects marked with `#[canopen(persist)]` are saved to a [`ParameterStorage`].
+
+use crate::nmt::NmtReset;
+use crate::objectdictionary::datalink::{BasicData, BasicReadData, BasicWriteData};
+use crate::objectdictionary::{ODError, OdInfo};
+
+/// Signature that has to be written to store parameters: "save" in ASCII
+pub const SAVE_SIGNATURE: u32 = u32::from_le_bytes(*b"save");
+
+/// Signature that has to be written to restore default parameters: "load" in ASCII
+pub const LOAD_SIGNATURE: u32 = u32::from_le_bytes(*b"load");
+
+/// Largest value of a persistent object in bytes
+pub const MAX_PARAMETER_SIZE: usize = 256;
+
+/// Parameters that are restored together, selected by the subindex of 0x1011
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ParameterGroup {
+    /// All parameters (0x1000 to 0x9FFF)
+    All = 1,
+    /// Communication parameters (0x1000 to 0x1FFF)
+    Communication = 2,
+    /// Standardized device profile parameters (0x6000 to 0x9FFF)
+    Application = 3,
+    /// Manufacturer-specific parameters (0x2000 to 0x5FFF)
+    Manufacturer = 4,
+}
+
+impl ParameterGroup {
+    pub const GROUPS: [ParameterGroup; 4] = [
+        ParameterGroup::All,
+        ParameterGroup::Communication,
+        ParameterGroup::Application,
+        ParameterGroup::Manufacturer,
+    ];
+
+    pub fn from_subindex(subindex: u8) -> Option<Self> {
+        ParameterGroup::GROUPS
+            .get(subindex.checked_sub(1)? as usize)
+            .copied()
+    }
+
+    pub fn subindex(self) -> u8 {
+        self as u8
+    }
+
+    pub fn contains(self, index: u16) -> bool {
+        match self {
+            ParameterGroup::All => (0x1000..=0x9FFF).contains(&index),
+            ParameterGroup::Communication => (0x1000..=0x1FFF).contains(&index),
+            ParameterGroup::Application => (0x6000..=0x9FFF).contains(&index),
+            ParameterGroup::Manufacturer => (0x2000..=0x5FFF).contains(&index),
+        }
+    }
+
+    /// The group reloaded from the storage on `reset`
+    pub fn reset_by(reset: NmtReset) -> Self {
+        match reset {
+            NmtReset::ResetApplication => ParameterGroup::All,
+            NmtReset::ResetCommunication => ParameterGroup::Communication,
+        }
+    }
+}
+
+/// Read sub-index 0 to 4 of object 0x1010 and 0x1011
+fn read_command(subindex: u8) -> Result<BasicReadData, ODError> {
+    match subindex {
+        0 => Ok((ParameterGroup::GROUPS.len() as u8).into()),
+        // the device saves/restores parameters on command
+        _ if ParameterGroup::from_subindex(subindex).is_some() => Ok(1u32.into()),
+        _ => Err(ODError::SubindexDoesNotExist),
+    }
+}
+
+/// The group selected by writing `signature` to object 0x1010 or 0x1011
+fn write_command(data: BasicWriteData, signature: u32) -> Result<ParameterGroup, ODError> {
+    if data.subindex() == 0 {
+        return Err(ODError::ReadOnlyError);
+    }
+    let group =
+        ParameterGroup::from_subindex(data.subindex()).ok_or(ODError::SubindexDoesNotExist)?;
+    if u32::try_from(data)? != signature {
+        return Err(ODError::TransferOrStorageError);
+    }
+    Ok(group)
+}
+
+/// Object 0x1010, saving persistent objects to a [`ParameterStorage`]
+///
+/// Writing [`SAVE_SIGNATURE`] to a subindex requests saving a [`ParameterGroup`].
+/// [`Node`](crate::node::Node) saves the parameters before confirming the write, if the object
+/// is available through [`NodeData::store_parameters`](crate::node::NodeData::store_parameters)
+/// and the storage through [`StorageCallback::parameter_storage`].
+#[derive(Default)]
+pub struct StoreParameters {
+    /// Bit n is set if subindex n was requested
+    pending: u8,
+}
+
+impl StoreParameters {
+    pub const INDEX: u16 = 0x1010;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_pending(&self, group: ParameterGroup) -> bool {
+        self.pending & (1 << group.subindex()) != 0
+    }
+
+    /// Take all requested groups.
+    pub fn take_pending(&mut self) -> impl Iterator<Item = ParameterGroup> {
+        let pending = core::mem::take(&mut self.pending);
+        ParameterGroup::GROUPS
+            .into_iter()
+            .filter(move |group| pending & (1 << group.subindex()) != 0)
+    }
+}
+
+impl BasicData for StoreParameters {
+    fn read(&mut self, _: u16, subindex: u8) -> Result<BasicReadData, ODError> {
+        read_command(subindex)
+    }
+
+    fn write(&mut self, data: BasicWriteData, _: OdInfo) -> Result<(), ODError> {
+        let group = write_command(data, SAVE_SIGNATURE)?;
+        self.pending |= 1 << group.subindex();
+        Ok(())
+    }
+}
+
+/// Object 0x1011, restoring the `default` values of the object dictionary
+///
+/// Writing [`LOAD_SIGNATURE`] to a subindex requests the restore of a [`ParameterGroup`].
+/// As required by CiA 301, the defaults become valid with the next reset:
+/// communication parameters with "reset communication", all others with "reset node".
+/// Values saved in the [`ParameterStorage`] are removed at the same time.
+/// [`Node`](crate::node::Node) does this automatically, if the object is available through
+/// [`NodeData::restore_default_parameters`](crate::node::NodeData::restore_default_parameters).
+#[derive(Default)]
+pub struct RestoreDefaultParameters {
+    /// Bit n is set if subindex n was requested
+    pending: u8,
+}
+
+impl RestoreDefaultParameters {
+    pub const INDEX: u16 = 0x1011;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_pending(&self, group: ParameterGroup) -> bool {
+        self.pending & (1 << group.subindex()) != 0
+    }
+
+    /// Take the requested groups that become valid with `reset`.
+    ///
+    /// "Reset communication" takes the communication parameters of a requested
+    /// [`ParameterGroup::All`], the others stay pending until "reset node".
+    pub fn take_pending(&mut self, reset: NmtReset) -> impl Iterator<Item = ParameterGroup> {
+        let bit = |group: ParameterGroup| 1 << group.subindex();
+        let pending = match reset {
+            NmtReset::ResetApplication => core::mem::take(&mut self.pending),
+            NmtReset::ResetCommunication => {
+                if self.pending & bit(ParameterGroup::All) != 0 {
+                    self.pending &= !bit(ParameterGroup::All);
+                    self.pending |= bit(ParameterGroup::Communication)
+                        | bit(ParameterGroup::Application)
+                        | bit(ParameterGroup::Manufacturer);
+                }
+                let pending = self.pending & bit(ParameterGroup::Communication);
+                self.pending &= !pending;
+                pending
+            }
+        };
+        ParameterGroup::GROUPS
+            .into_iter()
+            .filter(move |group| pending & (1 << group.subindex()) != 0)
+    }
+}
+
+impl BasicData for RestoreDefaultParameters {
+    fn read(&mut self, _: u16, subindex: u8) -> Result<BasicReadData, ODError> {
+        read_command(subindex)
+    }
+
+    fn write(&mut self, data: BasicWriteData, _: OdInfo) -> Result<(), ODError> {
+        let group = write_command(data, LOAD_SIGNATURE)?;
+        self.pending |= 1 << group.subindex();
+        Ok(())
+    }
+}
+
+/// Non-volatile memory for the values of persistent objects
+///
+/// Errors are reported to the SDO client, so they should be
+/// [`ODError::TransferOrStorageError`], [`ODError::OutOfMemory`] or [`ODError::HardwareError`].
+pub trait ParameterStorage {
+    /// Save the value of an object, replacing a previously saved one.
+    fn save(&mut self, index: u16, subindex: u8, value: &[u8]) -> Result<(), ODError>;
+
+    /// Copy the saved value of an object into `buf`.
+    ///
+    /// Returns the size of the value, `None` if no value is saved.
+    fn load(&mut self, index: u16, subindex: u8, buf: &mut [u8]) -> Result<Option<usize>, ODError>;
+
+    /// Remove the saved value of an object, if there is one.
+    fn remove(&mut self, index: u16, subindex: u8) -> Result<(), ODError>;
+
+    /// Called after all values of a store or restore command are saved or removed
+    fn commit(&mut self) -> Result<(), ODError> {
+        Ok(())
+    }
+}
+
+/// Provides the [`ParameterStorage`] of a [`Node`](crate::node::Node)
+///
+/// The stored parameters are loaded before every boot-up: all of them at power-on
+/// and on "reset node", the communication parameters on "reset communication".
+pub trait StorageCallback {
+    /// `None` if parameters can't be stored
+    fn parameter_storage(&mut self) -> Option<&mut dyn ParameterStorage> {
+        None
+    }
+
+    /// Restoring the defaults or loading the stored values of `group` failed.
+    ///
+    /// The objects that failed keep their previous values.
+    fn on_parameter_error(&mut self, group: ParameterGroup, error: ODError) {
+        let _ = (group, error);
+    }
+}
+
+/// Parameters held in RAM, e.g. for testing
+///
+/// Also used by [`FileStorage`] to serialize the parameters:
+/// for every value, the index (2 bytes), the subindex, the size (2 bytes)
+/// and the value itself, with all numbers in little endian.
+#[cfg(feature = "std")]
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+pub struct MemoryStorage {
+    values: std::collections::BTreeMap<(u16, u8), Vec<u8>>,
+}
+
+#[cfg(feature = "std")]
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, index: u16, subindex: u8) -> Option<&[u8]> {
+        self.values.get(&(index, subindex)).map(Vec::as_slice)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for (&(index, subindex), value) in &self.values {
+            bytes.extend_from_slice(&index.to_le_bytes());
+            bytes.push(subindex);
+            // values are limited to MAX_PARAMETER_SIZE by `save`
+            bytes.extend_from_slice(&(value.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(value);
+        }
+        bytes
+    }
+
+    /// `None` if `bytes` are truncated
+    pub fn from_bytes(mut bytes: &[u8]) -> Option<Self> {
+        let mut storage = MemoryStorage::new();
+        while !bytes.is_empty() {
+            let (header, rest) = bytes.split_first_chunk::<5>()?;
+            let index = u16::from_le_bytes([header[0], header[1]]);
+            let size = u16::from_le_bytes([header[3], header[4]]) as usize;
+            let value = rest.get(..size)?;
+            storage.values.insert((index, header[2]), value.to_vec());
+            bytes = &rest[size..];
+        }
+        Some(storage)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ParameterStorage for MemoryStorage {
+    fn save(&mut self, index: u16, subindex: u8, value: &[u8]) -> Result<(), ODError> {
+        if value.len() > MAX_PARAMETER_SIZE {
+            return Err(ODError::OutOfMemory);
+        }
+        self.values.insert((index, subindex), value.to_vec());
+        Ok(())
+    }
+
+    fn load(&mut self, index: u16, subindex: u8, buf: &mut [u8]) -> Result<Option<usize>, ODError> {
+        let Some(value) = self.values.get(&(index, subindex)) else {
+            return Ok(None);
+        };
+        let buf = buf.get_mut(..value.len()).ok_or(ODError::OutOfMemory)?;
+        buf.copy_from_slice(value);
+        Ok(Some(value.len()))
+    }
+
+    fn remove(&mut self, index: u16, subindex: u8) -> Result<(), ODError> {
+        self.values.remove(&(index, subindex));
+        Ok(())
+    }
+}
+
+/// Parameters saved in a file, in the format of [`MemoryStorage`]
+///
+/// The file is rewritten as a whole on every commit.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct FileStorage {
+    path: std::path::PathBuf,
+    values: MemoryStorage,
+}
+
+#[cfg(feature = "std")]
+impl FileStorage {
+    /// Read the parameters saved in `path`; a missing file is treated as empty.
+    pub fn open(path: impl Into<std::path::PathBuf>) -> std::io::Result<Self> {
+        use std::io::{Error, ErrorKind};
+
+        let path = path.into();
+        let values = match std::fs::read(&path) {
+            Ok(bytes) => MemoryStorage::from_bytes(&bytes)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "truncated parameter file"))?,
+            Err(error) if error.kind() == ErrorKind::NotFound => MemoryStorage::new(),
+            Err(error) => return Err(error),
+        };
+        Ok(FileStorage { path, values })
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+}
+
+#[cfg(feature = "std")]
+impl ParameterStorage for FileStorage {
+    fn save(&mut self, index: u16, subindex: u8, value: &[u8]) -> Result<(), ODError> {
+        self.values.save(index, subindex, value)
+    }
+
+    fn load(&mut self, index: u16, subindex: u8, buf: &mut [u8]) -> Result<Option<usize>, ODError> {
+        self.values.load(index, subindex, buf)
+    }
+
+    fn remove(&mut self, index: u16, subindex: u8) -> Result<(), ODError> {
+        self.values.remove(index, subindex)
+    }
+
+    /// Write to a temporary file first, so a power loss can't leave a truncated file.
+    fn commit(&mut self) -> Result<(), ODError> {
+        let tmp_path = self.path.with_extension("tmp");
+        std::fs::write(&tmp_path, self.values.to_bytes())
+            .and_then(|()| std::fs::rename(&tmp_path, &self.path))
+            .map_err(|_| ODError::TransferOrStorageError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialization() {
+        let mut storage = MemoryStorage::new();
+        storage.save(0x2000, 1, &[1, 2, 3]).unwrap();
+        storage.save(0x1017, 0, &[]).unwrap();
+        let bytes = storage.to_bytes();
+        assert_eq!(bytes, [0x17, 0x10, 0, 0, 0, 0x00, 0x20, 1, 3, 0, 1, 2, 3]);
+        assert_eq!(MemoryStorage::from_bytes(&bytes), Some(storage));
+        assert_eq!(MemoryStorage::from_bytes(&bytes[..12]), None);
+        assert_eq!(MemoryStorage::from_bytes(&bytes[..3]), None);
+    }
+
+    #[test]
+    fn test_file_storage() {
+        let path = std::env::temp_dir().join(format!("canopen-{}.par", std::process::id()));
+        let mut storage = FileStorage::open(&path).unwrap();
+        assert_eq!(storage.load(0x2000, 0, &mut [0; 4]), Ok(None));
+
+        storage.save(0x2000, 0, &[1, 2, 3, 4]).unwrap();
+        storage.commit().unwrap();
+
+        let mut buf = [0; 8];
+        let mut storage = FileStorage::open(&path).unwrap();
+        assert_eq!(storage.load(0x2000, 0, &mut buf), Ok(Some(4)));
+        assert_eq!(buf[..4], [1, 2, 3, 4]);
+        assert_eq!(
+            storage.load(0x2000, 0, &mut buf[..2]),
+            Err(ODError::OutOfMemory)
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
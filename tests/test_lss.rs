@@ -0,0 +1,356 @@
+use core::time::Duration;
+
+use embedded_can::Frame;
+
+use canopen::lss::master::{FastScan, FastScanResult, LssError, LssResult};
+use canopen::lss::{
+    FastScanParticipation, Identity, Lss, LssCallback, LssMaster, StoreConfigurationError,
+};
+use canopen::{LssMessage, NodeId};
+use frame::CanOpenFrame;
+
+mod frame;
+
+#[derive(Default)]
+struct Callback {
+    stored: Option<NodeId>,
+    new_node_id: Option<NodeId>,
+}
+
+impl LssCallback for Callback {
+    fn store_configuration(&mut self, node_id: NodeId) -> Result<(), StoreConfigurationError> {
+        self.stored = Some(node_id);
+        Ok(())
+    }
+
+    fn on_new_node_id(&mut self, node_id: NodeId) {
+        self.new_node_id = Some(node_id);
+    }
+}
+
+/// LSS slaves connected to an LSS master
+struct Bus {
+    slaves: Vec<(Lss, Callback)>,
+    now: Duration,
+}
+
+impl Bus {
+    fn new(slaves: impl IntoIterator<Item = (Option<NodeId>, Identity)>) -> Self {
+        Bus {
+            slaves: slaves
+                .into_iter()
+                .map(|(node_id, identity)| (Lss::new(node_id, identity), Callback::default()))
+                .collect(),
+            now: Duration::ZERO,
+        }
+    }
+
+    fn send(&mut self, master: &LssMaster, message: LssMessage) {
+        let frame: CanOpenFrame = message.into_frame();
+        for (lss, callback) in &mut self.slaves {
+            if let Some(response) = lss.on_message(&frame, callback) {
+                master.on_message(&response);
+            }
+        }
+    }
+
+    /// Apply the configured node-IDs like a "reset communication" of every slave
+    fn reset_communication(&mut self) {
+        for (lss, callback) in &mut self.slaves {
+            if let Some(node_id) = callback.new_node_id {
+                lss.set_active_node_id(Some(node_id));
+            }
+        }
+    }
+
+    fn wait(&mut self) {
+        self.now += Duration::from_millis(1);
+    }
+}
+
+fn master() -> LssMaster {
+    LssMaster::new(Duration::from_millis(10))
+}
+
+fn run<T>(
+    bus: &mut Bus,
+    master: &LssMaster,
+    mut poll: impl FnMut(Duration) -> Result<LssResult<T>, LssError>,
+) -> Result<T, LssError> {
+    loop {
+        match poll(bus.now)? {
+            LssResult::NextRequest(message) => bus.send(master, message),
+            LssResult::Waiting => bus.wait(),
+            LssResult::Done(result) => return Ok(result),
+        }
+    }
+}
+
+/// Poll the Fast Scan until it is finished and return all assigned node-IDs
+fn scan(
+    bus: &mut Bus,
+    master: &LssMaster,
+    fast_scan: &mut FastScan,
+) -> Result<Vec<(Identity, NodeId)>, LssError> {
+    let mut assigned = Vec::new();
+    loop {
+        match fast_scan.poll(bus.now)? {
+            FastScanResult::NextRequest(message) => bus.send(master, message),
+            FastScanResult::Waiting => bus.wait(),
+            FastScanResult::Assigned { identity, node_id } => assigned.push((identity, node_id)),
+            FastScanResult::Finished => return Ok(assigned),
+        }
+    }
+}
+
+fn identity(serial_number: u32) -> Identity {
+    Identity {
+        vendor_id: 0x0000_0123,
+        product_code: 0x0000_4567,
+        revision_number: 0x0001_0002,
+        serial_number,
+    }
+}
+
+#[test]
+fn test_configure_selected_slave() {
+    let master = master();
+    let mut bus = Bus::new([(None, identity(1)), (None, identity(2))]);
+
+    let mut request = master.switch_selective(&identity(2));
+    run(&mut bus, &master, |now| request.poll(now)).unwrap();
+
+    let mut request = master.inquire_identity();
+    assert_eq!(
+        run(&mut bus, &master, |now| request.poll(now)),
+        Ok(identity(2))
+    );
+
+    let mut request = master.inquire_node_id();
+    assert_eq!(run(&mut bus, &master, |now| request.poll(now)), Ok(None));
+
+    let mut request = master.configure_node_id(NodeId::NODE_ID_5);
+    run(&mut bus, &master, |now| request.poll(now)).unwrap();
+
+    let mut request = master.store_configuration();
+    run(&mut bus, &master, |now| request.poll(now)).unwrap();
+    assert_eq!(bus.slaves[1].1.stored, Some(NodeId::NODE_ID_5));
+
+    let message = master.switch_global_waiting();
+    bus.send(&master, message);
+    assert_eq!(bus.slaves[0].1.new_node_id, None);
+    assert_eq!(bus.slaves[1].1.new_node_id, Some(NodeId::NODE_ID_5));
+
+    // no slave is in the "LSS configuration" state anymore
+    let mut request = master.inquire_node_id();
+    assert_eq!(
+        run(&mut bus, &master, |now| request.poll(now)),
+        Err(LssError::Timeout)
+    );
+}
+
+#[test]
+fn test_configure_bit_timing_error() {
+    let master = master();
+    let mut bus = Bus::new([(None, identity(1))]);
+    let message = master.switch_global_configuration();
+    bus.send(&master, message);
+
+    let mut request = master.configure_bit_timing(0, 4);
+    assert_eq!(
+        run(&mut bus, &master, |now| request.poll(now)),
+        Err(LssError::Failed {
+            error: 0x01,
+            specific_error: 0
+        })
+    );
+}
+
+#[test]
+fn test_identify_non_configured() {
+    let master = master();
+    let mut bus = Bus::new([(Some(NodeId::NODE_ID_1), identity(1))]);
+    let mut request = master.identify_non_configured();
+    assert_eq!(run(&mut bus, &master, |now| request.poll(now)), Ok(false));
+
+    let mut bus = Bus::new([(Some(NodeId::NODE_ID_1), identity(1)), (None, identity(2))]);
+    let mut request = master.identify_non_configured();
+    assert_eq!(run(&mut bus, &master, |now| request.poll(now)), Ok(true));
+}
+
+#[test]
+fn test_fast_scan() {
+    let lss_address = Identity {
+        vendor_id: 0xA5A5_5A5A,
+        product_code: 0x8000_0001,
+        revision_number: 0,
+        serial_number: u32::MAX,
+    };
+    let master = master();
+    let mut bus = Bus::new([(None, lss_address.clone())]);
+
+    let mut fast_scan = master.fast_scan(NodeId::NODE_ID_10, true);
+    let assigned = loop {
+        match fast_scan.poll(bus.now).unwrap() {
+            FastScanResult::NextRequest(message) => bus.send(&master, message),
+            FastScanResult::Waiting => bus.wait(),
+            FastScanResult::Assigned { identity, node_id } => break (identity, node_id),
+            FastScanResult::Finished => panic!("slave not found"),
+        }
+    };
+
+    assert_eq!(assigned, (lss_address, NodeId::NODE_ID_10));
+    assert_eq!(bus.slaves[0].1.stored, Some(NodeId::NODE_ID_10));
+    assert_eq!(bus.slaves[0].1.new_node_id, Some(NodeId::NODE_ID_10));
+}
+
+#[test]
+fn test_fast_scan_without_slaves() {
+    let master = master();
+    let mut bus = Bus::new([]);
+
+    let mut fast_scan = master.fast_scan(NodeId::NODE_ID_10, false);
+    loop {
+        match fast_scan.poll(bus.now).unwrap() {
+            FastScanResult::NextRequest(message) => bus.send(&master, message),
+            FastScanResult::Waiting => bus.wait(),
+            FastScanResult::Assigned { .. } => panic!("no slave expected"),
+            FastScanResult::Finished => break,
+        }
+    }
+    assert!(bus.now >= master.timeout);
+}
+
+#[test]
+fn test_fast_scan_multiple_slaves() {
+    let unconfigured = [
+        identity(0x0000_0000),
+        identity(0x0000_0001),
+        identity(0x8000_0000),
+        identity(0xFFFF_FFFF),
+        Identity {
+            revision_number: 0x0001_0003,
+            ..identity(0x0000_0001)
+        },
+    ];
+    let configured = identity(0x1234_5678);
+    let master = master();
+    let mut bus = Bus::new(
+        unconfigured
+            .iter()
+            .map(|identity| (None, identity.clone()))
+            .chain([(Some(NodeId::NODE_ID_1), configured)]),
+    );
+
+    let assigned = scan(
+        &mut bus,
+        &master,
+        &mut master.fast_scan(NodeId::NODE_ID_10, false),
+    )
+    .unwrap();
+    assert_eq!(assigned.len(), unconfigured.len());
+
+    // every slave got a distinct node-ID
+    for (n, (identity, node_id)) in assigned.iter().enumerate() {
+        assert_eq!(node_id.raw(), 10 + n as u8);
+        let slave = unconfigured.iter().position(|i| i == identity).unwrap();
+        assert_eq!(bus.slaves[slave].1.new_node_id, Some(*node_id));
+    }
+    assert_eq!(bus.slaves[5].1.new_node_id, None);
+
+    // the slaves count as non-configured until they apply the new node-ID
+    let mut request = master.identify_non_configured();
+    assert_eq!(run(&mut bus, &master, |now| request.poll(now)), Ok(true));
+
+    // nothing left to configure
+    bus.reset_communication();
+    let mut request = master.identify_non_configured();
+    assert_eq!(run(&mut bus, &master, |now| request.poll(now)), Ok(false));
+    assert_eq!(
+        scan(
+            &mut bus,
+            &master,
+            &mut master.fast_scan(NodeId::NODE_ID_20, false)
+        ),
+        Ok(Vec::new())
+    );
+}
+
+#[test]
+fn test_fast_scan_participation() {
+    let master = master();
+    let mut bus = Bus::new([
+        (Some(NodeId::NODE_ID_1), identity(3)),
+        (None, identity(2)),
+        (None, identity(1)),
+    ]);
+    bus.slaves[0].0.fast_scan_participation = FastScanParticipation::Always;
+    bus.slaves[2].0.fast_scan_participation = FastScanParticipation::Never;
+
+    let found = scan(
+        &mut bus,
+        &master,
+        &mut master.fast_scan(NodeId::NODE_ID_10, false),
+    )
+    .unwrap();
+    // every slave is found once, those with the lowest LSS address first
+    let found: Vec<_> = found.into_iter().map(|(identity, _)| identity).collect();
+    assert_eq!(found, [identity(2), identity(3)]);
+    assert_eq!(bus.slaves[2].1.new_node_id, None);
+
+    // found slaves take part again after being switched to configuration globally
+    bus.reset_communication();
+    bus.send(&master, master.switch_global_configuration());
+    bus.send(&master, master.switch_global_waiting());
+    let found = scan(
+        &mut bus,
+        &master,
+        &mut master.fast_scan(NodeId::NODE_ID_20, false),
+    )
+    .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].0, identity(3));
+}
+
+#[test]
+fn test_fast_scan_restart() {
+    let master = master();
+    let mut bus = Bus::new([(None, identity(1)), (None, identity(2))]);
+
+    // abort a scan half way through the vendor-ID
+    let mut fast_scan = master.fast_scan(NodeId::NODE_ID_10, false);
+    for _ in 0..40 {
+        match fast_scan.poll(bus.now).unwrap() {
+            FastScanResult::NextRequest(message) => bus.send(&master, message),
+            FastScanResult::Waiting => bus.wait(),
+            _ => panic!("scan should not be done yet"),
+        }
+    }
+
+    let assigned = scan(
+        &mut bus,
+        &master,
+        &mut master.fast_scan(NodeId::NODE_ID_10, false),
+    )
+    .unwrap();
+    assert_eq!(assigned.len(), 2);
+}
+
+#[test]
+fn test_fast_scan_requires_reset() {
+    let mut bus = Bus::new([(None, identity(1))]);
+    let (lss, callback) = &mut bus.slaves[0];
+    let mut fast_scan = |bit_checked: u8, lss_sub: u8| {
+        let mut request = [0x51, 0, 0, 0, 0, bit_checked, lss_sub, lss_sub];
+        request[1..5].copy_from_slice(&0x0000_0123u32.to_le_bytes());
+        let frame = CanOpenFrame::new(Lss::LSS_REQUEST_ID, &request).unwrap();
+        lss.on_message(&frame, callback).is_some()
+    };
+
+    // the vendor-ID matches, but no scan was started
+    assert!(!fast_scan(0, 0));
+    assert!(fast_scan(128, 0));
+    assert!(fast_scan(0, 0));
+    // wrong LSS sub
+    assert!(!fast_scan(0, 1));
+}
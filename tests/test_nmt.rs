@@ -0,0 +1,242 @@
+use embedded_can::Frame;
+
+use canopen::nmt::{
+    CommunicationObject, Nmt, NmtCallback, NmtMaster, NmtRequest, NmtReset, NmtResetCallback,
+    NmtStartup, NmtState,
+};
+use canopen::objectdictionary::object::{ObjectFlags, ObjectInfo, PdoSize};
+use canopen::objectdictionary::{OdData, OdPosition};
+use canopen::pdo::{DefaultTPDO, TPDO};
+use canopen::sdo::SdoServer;
+use canopen::NodeId;
+use frame::CanOpenFrame;
+
+mod frame;
+
+struct DefaultNmtCallback;
+
+impl NmtCallback for DefaultNmtCallback {}
+
+#[test]
+fn test_nmt_master_commands() {
+    let master = NmtMaster::new();
+    let mut nmt = Nmt::new(NodeId::NODE_ID_2);
+
+    let frame: CanOpenFrame = master
+        .request(NmtRequest::StartRemoteNode, NodeId::NODE_ID_2)
+        .into_frame();
+    assert_eq!(frame.data(), [0x01, 0x02]);
+    nmt.on_message(&frame, &mut DefaultNmtCallback);
+    assert_eq!(nmt.state, NmtState::Operational);
+
+    // addressed to another node
+    let frame: CanOpenFrame = master
+        .request(NmtRequest::StopRemoteNode, NodeId::NODE_ID_3)
+        .into_frame();
+    nmt.on_message(&frame, &mut DefaultNmtCallback);
+    assert_eq!(nmt.state, NmtState::Operational);
+
+    let frame: CanOpenFrame = master.broadcast(NmtRequest::StopRemoteNode).into_frame();
+    assert_eq!(frame.data(), [0x02, 0x00]);
+    nmt.on_message(&frame, &mut DefaultNmtCallback);
+    assert_eq!(nmt.state, NmtState::Stopped);
+}
+
+#[test]
+fn test_nmt_master_state_table() {
+    let mut master = NmtMaster::new();
+    let mut nmt = Nmt::new(NodeId::NODE_ID_5);
+
+    let boot_up: CanOpenFrame = nmt.boot_up_message();
+    assert_eq!(
+        master.on_message(&boot_up),
+        Some((NodeId::NODE_ID_5, NmtState::Initialisation))
+    );
+
+    let heartbeat = CanOpenFrame::new(NodeId::NODE_ID_5.heartbeat_cobid(), &[0x7F]).unwrap();
+    master.on_message(&heartbeat);
+    assert_eq!(
+        master.state(NodeId::NODE_ID_5),
+        Some(NmtState::PreOperational)
+    );
+
+    // node guarding response with toggle bit set
+    let heartbeat = CanOpenFrame::new(NodeId::NODE_ID_7.heartbeat_cobid(), &[0x85]).unwrap();
+    master.on_message(&heartbeat);
+    assert_eq!(master.state(NodeId::NODE_ID_7), Some(NmtState::Operational));
+
+    // unknown state
+    let heartbeat = CanOpenFrame::new(NodeId::NODE_ID_8.heartbeat_cobid(), &[0x33]).unwrap();
+    assert_eq!(master.on_message(&heartbeat), None);
+    assert_eq!(master.state(NodeId::NODE_ID_8), None);
+
+    assert_eq!(
+        master.nodes().collect::<Vec<_>>(),
+        [
+            (NodeId::NODE_ID_5, NmtState::PreOperational),
+            (NodeId::NODE_ID_7, NmtState::Operational),
+        ]
+    );
+
+    master.clear_state(NodeId::NODE_ID_5);
+    assert_eq!(master.state(NodeId::NODE_ID_5), None);
+}
+
+#[test]
+fn test_communication_objects_per_state() {
+    use CommunicationObject::*;
+
+    let allowed = |state: NmtState| {
+        [BootUp, Pdo, Sdo, Sync, Time, Emcy, Nmt, ErrorControl]
+            .into_iter()
+            .filter(|&object| state.allows(object))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(allowed(NmtState::Initialisation), [BootUp]);
+    assert_eq!(
+        allowed(NmtState::PreOperational),
+        [Sdo, Sync, Time, Emcy, Nmt, ErrorControl]
+    );
+    assert_eq!(
+        allowed(NmtState::Operational),
+        [Pdo, Sdo, Sync, Time, Emcy, Nmt, ErrorControl]
+    );
+    assert_eq!(allowed(NmtState::Stopped), [Nmt, ErrorControl]);
+}
+
+#[test]
+fn test_sdo_ignored_when_stopped() {
+    #[derive(OdData)]
+    struct Data {
+        #[canopen(index = 0x2000)]
+        obj: u32,
+    }
+
+    let mut od = Data { obj: 1 }.into_od();
+    let mut nmt = Nmt::new(NodeId::NODE_ID_2);
+    let mut sdo_server = SdoServer::new(NodeId::NODE_ID_2);
+    let upload =
+        CanOpenFrame::new(sdo_server.rx_cobid, &[0x40, 0x00, 0x20, 0, 0, 0, 0, 0]).unwrap();
+
+    nmt.on_request::<CanOpenFrame>(
+        NmtRequest::EnterPreOperational as u8,
+        &mut DefaultNmtCallback,
+    );
+    let response = nmt.gate(CommunicationObject::Sdo, || {
+        sdo_server.on_message(&upload, &mut od)
+    });
+    assert!(response.is_some());
+
+    nmt.on_request::<CanOpenFrame>(NmtRequest::StopRemoteNode as u8, &mut DefaultNmtCallback);
+    let response = nmt.gate(CommunicationObject::Sdo, || {
+        sdo_server.on_message(&upload, &mut od)
+    });
+    assert!(response.is_none());
+}
+
+#[test]
+fn test_reset_hooks() {
+    #[derive(OdData)]
+    struct Data {
+        #[canopen(index = 0x1800, subindex = 1)]
+        #[canopen(index = 0x1A00, subindex = 0)]
+        #[canopen(index = 0x1A00, subindex = 1)]
+        tpdo: TPDO,
+        #[canopen(index = 0x2000)]
+        setpoint: u32,
+    }
+
+    struct Reset;
+
+    impl NmtResetCallback<Data> for Reset {
+        fn reset_application(&mut self, data: &mut Data) {
+            data.setpoint = 0;
+        }
+
+        fn reset_communication(&mut self, data: &mut Data) {
+            data.tpdo.reset();
+        }
+    }
+
+    let mut od = Data {
+        tpdo: DefaultTPDO::TPDO1.new(NodeId::NODE_ID_2, |_, new| Ok(new)),
+        setpoint: 42,
+    }
+    .into_od();
+    let mut nmt = Nmt::new(NodeId::NODE_ID_2);
+    nmt.state = NmtState::Operational;
+
+    let info = ObjectInfo {
+        index: 0x2000,
+        subindex: 0,
+        flags: ObjectFlags::empty().set_pdo_size(PdoSize::Four),
+        od_position: OdPosition(0),
+    };
+    od.data.tpdo.map.map_object(0, info, 32).unwrap();
+    assert_eq!(od.data.tpdo.map.get_map_data_packed(1), 0x2000_0020);
+
+    // reset communication keeps application values
+    nmt.on_request::<CanOpenFrame>(
+        NmtRequest::ResetCommunication as u8,
+        &mut DefaultNmtCallback,
+    );
+    assert_eq!(nmt.state, NmtState::Initialisation);
+    assert_eq!(nmt.pending_reset(), Some(NmtReset::ResetCommunication));
+
+    let boot_up: CanOpenFrame = nmt.reset(&mut od, &mut Reset).unwrap();
+    assert_eq!(boot_up.id(), NodeId::NODE_ID_2.heartbeat_cobid().into());
+    assert_eq!(boot_up.data(), [0x00]);
+    assert_eq!(nmt.state, NmtState::PreOperational);
+    assert_eq!(nmt.pending_reset(), None);
+    assert_eq!(od.data.tpdo.map.get_map_data_packed(1), 0);
+    assert_eq!(od.data.setpoint, 42);
+
+    // nothing left to do
+    assert!(nmt
+        .reset::<CanOpenFrame, _, 4>(&mut od, &mut Reset)
+        .is_none());
+
+    // reset node resets the application as well
+    nmt.on_request::<CanOpenFrame>(NmtRequest::ResetNode as u8, &mut DefaultNmtCallback);
+    assert_eq!(nmt.pending_reset(), Some(NmtReset::ResetApplication));
+    assert!(nmt
+        .reset::<CanOpenFrame, _, 4>(&mut od, &mut Reset)
+        .is_some());
+    assert_eq!(od.data.setpoint, 0);
+}
+
+#[test]
+fn test_boot_up() {
+    #[derive(OdData)]
+    struct Slave {
+        #[canopen(index = 0x2000)]
+        setpoint: u32,
+    }
+
+    #[derive(OdData)]
+    struct SelfStarting {
+        #[canopen(index = 0x1F80)]
+        nmt_startup: u32,
+    }
+
+    let mut nmt = Nmt::new(NodeId::NODE_ID_3);
+    assert_eq!(nmt.state, NmtState::Initialisation);
+    let boot_up: CanOpenFrame = nmt.boot_up(&mut Slave { setpoint: 0 }.into_od());
+    assert_eq!(boot_up.id(), NodeId::NODE_ID_3.heartbeat_cobid().into());
+    assert_eq!(boot_up.data(), [0x00]);
+    assert_eq!(nmt.state, NmtState::PreOperational);
+
+    let mut od = SelfStarting {
+        nmt_startup: NmtStartup::NO_AUTO_OPERATIONAL,
+    }
+    .into_od();
+    let mut nmt = Nmt::new(NodeId::NODE_ID_3);
+    let _: CanOpenFrame = nmt.boot_up(&mut od);
+    assert_eq!(nmt.state, NmtState::PreOperational);
+
+    od.data.nmt_startup = 0;
+    let mut nmt = Nmt::new(NodeId::NODE_ID_3);
+    let _: CanOpenFrame = nmt.boot_up(&mut od);
+    assert_eq!(nmt.state, NmtState::Operational);
+}
@@ -0,0 +1,678 @@
+use core::time::Duration;
+
+use embedded_can::Frame;
+
+use canopen::lss::{Identity, Lss, LssCallback, StoreConfigurationError};
+use canopen::nmt::{NmtCallback, NmtRequest, NmtResetCallback, NmtState};
+use canopen::node::{Node, NodeData};
+use canopen::objectdictionary::{ODError, OdData};
+use canopen::pdo::{DefaultRPDO, DefaultTPDO, RPDO, TPDO};
+use canopen::sdo::client::download_request;
+use canopen::sdo::SdoServer;
+use canopen::storage::{
+    MemoryStorage, ParameterGroup, ParameterStorage, RestoreDefaultParameters, StorageCallback,
+    StoreParameters, LOAD_SIGNATURE, SAVE_SIGNATURE,
+};
+use canopen::NodeId;
+use frame::CanOpenFrame;
+
+mod frame;
+
+const NODE_ID: NodeId = NodeId::NODE_ID_2;
+
+#[derive(OdData)]
+struct Data {
+    #[canopen(index = 0x1010, subindex = 0, read_only)]
+    #[canopen(index = 0x1010, subindex = 1)]
+    #[canopen(index = 0x1010, subindex = 2)]
+    #[canopen(index = 0x1010, subindex = 3)]
+    #[canopen(index = 0x1010, subindex = 4)]
+    store: StoreParameters,
+    #[canopen(index = 0x1011, subindex = 0, read_only)]
+    #[canopen(index = 0x1011, subindex = 1)]
+    #[canopen(index = 0x1011, subindex = 2)]
+    #[canopen(index = 0x1011, subindex = 3)]
+    #[canopen(index = 0x1011, subindex = 4)]
+    restore: RestoreDefaultParameters,
+    #[canopen(index = 0x1017, default = 1000, persist)]
+    heartbeat_producer_time: u16,
+    #[canopen(index = 0x1400, subindex = 0x01)]
+    #[canopen(index = 0x1400, subindex = 0x02)]
+    #[canopen(index = 0x1400, subindex = 0x03)]
+    #[canopen(index = 0x1400, subindex = 0x05)]
+    #[canopen(index = 0x1400, subindex = 0x06)]
+    #[canopen(index = 0x1600, subindex = 0x00)]
+    #[canopen(index = 0x1600, subindex = 0x01)]
+    rpdo: RPDO,
+    #[canopen(index = 0x1800, subindex = 0x01)]
+    #[canopen(index = 0x1800, subindex = 0x02)]
+    #[canopen(index = 0x1800, subindex = 0x03)]
+    #[canopen(index = 0x1800, subindex = 0x05)]
+    #[canopen(index = 0x1800, subindex = 0x06)]
+    #[canopen(index = 0x1A00, subindex = 0x00)]
+    #[canopen(index = 0x1A00, subindex = 0x01)]
+    tpdo: TPDO,
+    #[canopen(index = 0x2000, default = 7, persist)]
+    value: u32,
+}
+
+impl NodeData for Data {
+    fn tpdo(&mut self, num: usize) -> Option<&mut TPDO> {
+        match num {
+            0 => Some(&mut self.tpdo),
+            _ => None,
+        }
+    }
+
+    fn rpdo(&mut self, num: usize) -> Option<&mut RPDO> {
+        match num {
+            0 => Some(&mut self.rpdo),
+            _ => None,
+        }
+    }
+
+    fn store_parameters(&mut self) -> Option<&mut StoreParameters> {
+        Some(&mut self.store)
+    }
+
+    fn restore_default_parameters(&mut self) -> Option<&mut RestoreDefaultParameters> {
+        Some(&mut self.restore)
+    }
+}
+
+#[derive(Default)]
+struct Callback {
+    storage: Option<MemoryStorage>,
+    parameter_errors: Vec<(ParameterGroup, ODError)>,
+}
+
+impl NmtCallback for Callback {}
+
+impl NmtResetCallback<Data> for Callback {
+    fn reset_application(&mut self, data: &mut Data) {
+        data.value = 0;
+    }
+
+    fn reset_communication(&mut self, data: &mut Data) {
+        data.heartbeat_producer_time = 0;
+        data.rpdo.reset();
+        data.tpdo.reset();
+    }
+}
+
+impl LssCallback for Callback {
+    fn store_configuration(&mut self, _: NodeId) -> Result<(), StoreConfigurationError> {
+        Err(StoreConfigurationError::NotSupported)
+    }
+
+    fn on_new_node_id(&mut self, _: NodeId) {}
+}
+
+impl StorageCallback for Callback {
+    fn parameter_storage(&mut self) -> Option<&mut dyn ParameterStorage> {
+        self.storage.as_mut().map(|storage| storage as _)
+    }
+
+    fn on_parameter_error(&mut self, group: ParameterGroup, error: ODError) {
+        self.parameter_errors.push((group, error));
+    }
+}
+
+fn node() -> Node<Data, 26> {
+    let od = Data {
+        store: StoreParameters::new(),
+        restore: RestoreDefaultParameters::new(),
+        heartbeat_producer_time: 0,
+        rpdo: DefaultRPDO::RPDO1.new(NODE_ID, |_, new| Ok(new)),
+        tpdo: DefaultTPDO::TPDO1.new(NODE_ID, |_, new| Ok(new)),
+        value: 0x04030201,
+    }
+    .into_od();
+    Node::new(NODE_ID, od)
+}
+
+fn process(node: &mut Node<Data, 26>, frame: CanOpenFrame) -> Vec<CanOpenFrame> {
+    process_with(node, frame, &mut Callback::default())
+}
+
+fn process_with(
+    node: &mut Node<Data, 26>,
+    frame: CanOpenFrame,
+    callback: &mut Callback,
+) -> Vec<CanOpenFrame> {
+    let mut outbox = Vec::new();
+    node.process(&frame, callback, &mut outbox);
+    outbox
+}
+
+fn tick(node: &mut Node<Data, 26>, ms: u64) -> Vec<CanOpenFrame> {
+    tick_with(node, ms, &mut Callback::default())
+}
+
+fn tick_with(node: &mut Node<Data, 26>, ms: u64, callback: &mut Callback) -> Vec<CanOpenFrame> {
+    let mut outbox = Vec::new();
+    node.tick(Duration::from_millis(ms), callback, &mut outbox);
+    outbox
+}
+
+fn nmt(request: NmtRequest) -> CanOpenFrame {
+    CanOpenFrame::new(
+        embedded_can::StandardId::ZERO,
+        &[request as u8, NODE_ID.raw()],
+    )
+    .unwrap()
+}
+
+fn download(
+    index: u16,
+    subindex: u8,
+    value: impl canopen::sdo::client::ExpeditedSdoValue,
+) -> CanOpenFrame {
+    CanOpenFrame::new(
+        NODE_ID.sdo_rx_cobid(),
+        &download_request(index, subindex, value),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_boot_up_and_heartbeat() {
+    let mut node = node();
+    node.od.data.heartbeat_producer_time = 100;
+
+    let frames = tick(&mut node, 0);
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].id(), NODE_ID.heartbeat_cobid().into());
+    assert_eq!(frames[0].data(), [0x00]);
+    assert_eq!(frames[1].data(), [NmtState::PreOperational as u8]);
+    assert_eq!(node.nmt.state, NmtState::PreOperational);
+
+    assert!(tick(&mut node, 50).is_empty());
+    assert_eq!(tick(&mut node, 100).len(), 1);
+}
+
+#[test]
+fn test_sdo_depends_on_nmt_state() {
+    let mut node = node();
+    tick(&mut node, 0);
+
+    let responses = process(&mut node, download(0x2000, 0, 42u32));
+    assert_eq!(responses.len(), 1);
+    assert_eq!(node.od.data.value, 42);
+
+    assert!(process(&mut node, nmt(NmtRequest::StopRemoteNode)).is_empty());
+    assert!(process(&mut node, download(0x2000, 0, 43u32)).is_empty());
+    assert_eq!(node.od.data.value, 42);
+}
+
+#[test]
+fn test_reset_sends_boot_up() {
+    let mut node = node();
+    node.od.data.heartbeat_producer_time = 100;
+    tick(&mut node, 0);
+    process(&mut node, nmt(NmtRequest::StartRemoteNode));
+    assert_eq!(node.nmt.state, NmtState::Operational);
+
+    let frames = process(&mut node, nmt(NmtRequest::ResetCommunication));
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].data(), [0x00]);
+    assert_eq!(node.nmt.state, NmtState::PreOperational);
+    assert_eq!(node.od.data.heartbeat_producer_time, 0);
+    assert_eq!(node.od.data.value, 0x04030201);
+
+    process(&mut node, nmt(NmtRequest::ResetNode));
+    assert_eq!(node.od.data.value, 0);
+}
+
+#[test]
+fn test_tpdo() {
+    let mut node = node();
+    tick(&mut node, 0);
+
+    // map 0x2000 and make the TPDO event-driven with an event timer of 100ms
+    for request in [
+        download(0x1A00, 1, 0x2000_0020u32),
+        download(0x1A00, 0, 1u8),
+        download(0x1800, 2, 0xFFu8),
+        download(0x1800, 5, 100u16),
+    ] {
+        let responses = process(&mut node, request);
+        assert_eq!(responses[0].data()[0], 0x60);
+    }
+
+    // PDOs are only sent in operational state
+    assert!(tick(&mut node, 10).is_empty());
+    process(&mut node, nmt(NmtRequest::StartRemoteNode));
+
+    // the event timer starts with the first tick in operational state
+    assert!(tick(&mut node, 20).is_empty());
+    assert!(tick(&mut node, 119).is_empty());
+    let frames = tick(&mut node, 120);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(
+        frames[0].id(),
+        DefaultTPDO::TPDO1.cob_id(NODE_ID, false, false).id
+    );
+    assert_eq!(frames[0].data(), [0x01, 0x02, 0x03, 0x04]);
+
+    assert!(tick(&mut node, 200).is_empty());
+    assert_eq!(tick(&mut node, 220).len(), 1);
+
+    node.od.data.tpdo.trigger();
+    assert_eq!(tick(&mut node, 221).len(), 1);
+}
+
+#[test]
+fn test_event_driven_tpdo_without_timer() {
+    let mut node = node();
+    tick(&mut node, 0);
+    for request in [
+        download(0x1A00, 1, 0x2000_0020u32),
+        download(0x1A00, 0, 1u8),
+        download(0x1800, 2, 0xFEu8),
+    ] {
+        process(&mut node, request);
+    }
+    process(&mut node, nmt(NmtRequest::StartRemoteNode));
+
+    // nothing is sent until the TPDO is triggered
+    for ms in [10, 1000, 100_000] {
+        assert!(tick(&mut node, ms).is_empty());
+    }
+    node.od.data.tpdo.trigger();
+    assert_eq!(tick(&mut node, 100_001).len(), 1);
+    assert!(tick(&mut node, 100_002).is_empty());
+}
+
+#[test]
+fn test_tpdo_on_change() {
+    let mut node = node();
+    tick(&mut node, 0);
+    for request in [
+        download(0x1A00, 1, 0x2000_0020u32),
+        download(0x1A00, 0, 1u8),
+        download(0x1800, 2, 0xFEu8),
+    ] {
+        process(&mut node, request);
+    }
+    process(&mut node, nmt(NmtRequest::StartRemoteNode));
+    tick(&mut node, 10);
+    node.od.clear_changed();
+
+    process(&mut node, download(0x2000, 0, 0x0A0B0C0Du32));
+    assert!(tick(&mut node, 20).is_empty());
+    while let Some(info) = node.od.take_changed() {
+        node.trigger_tpdos(info.od_position);
+    }
+    let frames = tick(&mut node, 30);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].data(), [0x0D, 0x0C, 0x0B, 0x0A]);
+}
+
+#[test]
+fn test_synchronous_tpdo() {
+    let mut node = node();
+    tick(&mut node, 0);
+    for request in [
+        download(0x1A00, 1, 0x2000_0020u32),
+        download(0x1A00, 0, 1u8),
+        download(0x1800, 2, 2u8),
+    ] {
+        process(&mut node, request);
+    }
+    process(&mut node, nmt(NmtRequest::StartRemoteNode));
+
+    let sync = CanOpenFrame::new(Node::<Data, 9>::SYNC_ID, &[]).unwrap();
+    assert!(process(&mut node, sync).is_empty());
+    let sync = CanOpenFrame::new(Node::<Data, 9>::SYNC_ID, &[]).unwrap();
+    assert_eq!(process(&mut node, sync).len(), 1);
+}
+
+fn rpdo(data: &[u8]) -> CanOpenFrame {
+    CanOpenFrame::new(DefaultRPDO::RPDO1.cob_id(NODE_ID, false).id, data).unwrap()
+}
+
+#[test]
+fn test_rpdo() {
+    let mut node = node();
+    tick(&mut node, 0);
+
+    // read-only objects can't receive data
+    let responses = process(&mut node, download(0x1600, 1, 0x1010_0008u32));
+    assert_eq!(responses[0].data()[0], 0x80);
+
+    for request in [
+        download(0x1600, 1, 0x2000_0020u32),
+        download(0x1600, 0, 1u8),
+        download(0x1400, 2, 0xFFu8),
+    ] {
+        let responses = process(&mut node, request);
+        assert_eq!(responses[0].data()[0], 0x60);
+    }
+
+    // PDOs are only received in operational state
+    assert!(process(&mut node, rpdo(&[1, 0, 0, 0])).is_empty());
+    assert_eq!(node.od.data.value, 0x04030201);
+
+    process(&mut node, nmt(NmtRequest::StartRemoteNode));
+    node.od.clear_changed();
+    assert!(process(&mut node, rpdo(&[0x0D, 0x0C, 0x0B, 0x0A])).is_empty());
+    assert_eq!(node.od.data.value, 0x0A0B0C0D);
+    assert_eq!(node.od.take_changed().map(|info| info.index), Some(0x2000));
+
+    // too short for the mapping
+    let frames = process(&mut node, rpdo(&[1, 2]));
+    assert_eq!(frames[0].id(), NODE_ID.emcy_cobid().into());
+    assert_eq!(frames[0].data(), [0x10, 0x82, 0x10, 0, 0, 0, 0, 0]);
+    assert_eq!(node.od.data.value, 0x0A0B0C0D);
+}
+
+#[test]
+fn test_synchronous_rpdo() {
+    let mut node = node();
+    tick(&mut node, 0);
+    for request in [
+        download(0x1600, 1, 0x2000_0020u32),
+        download(0x1600, 0, 1u8),
+        download(0x1400, 2, 0u8),
+    ] {
+        process(&mut node, request);
+    }
+    process(&mut node, nmt(NmtRequest::StartRemoteNode));
+
+    process(&mut node, rpdo(&[1, 0, 0, 0]));
+    process(&mut node, rpdo(&[2, 0, 0, 0]));
+    assert_eq!(node.od.data.value, 0x04030201);
+
+    // the data received last is written on the next SYNC
+    let sync = CanOpenFrame::new(Node::<Data, 9>::SYNC_ID, &[]).unwrap();
+    process(&mut node, sync);
+    assert_eq!(node.od.data.value, 2);
+}
+
+#[test]
+fn test_additional_sdo_server() {
+    let mut node = node();
+    let server = SdoServer::new(NodeId::NODE_ID_5);
+    let rx_cobid = server.rx_cobid;
+    assert!(node.sdo_servers.push(server).is_ok());
+    tick(&mut node, 0);
+
+    let request = CanOpenFrame::new(rx_cobid, &download_request(0x2000, 0, 42u32)).unwrap();
+    let responses = process(&mut node, request);
+    assert_eq!(responses[0].id(), NodeId::NODE_ID_5.sdo_tx_cobid().into());
+    assert_eq!(responses[0].data()[0], 0x60);
+    assert_eq!(node.od.data.value, 42);
+
+    // the default server is still available
+    let responses = process(&mut node, download(0x2000, 0, 43u32));
+    assert_eq!(responses[0].id(), NODE_ID.sdo_tx_cobid().into());
+    assert_eq!(node.od.data.value, 43);
+}
+
+#[test]
+fn test_sync_start_value() {
+    let mut node = node();
+    tick(&mut node, 0);
+    for request in [
+        download(0x1A00, 1, 0x2000_0020u32),
+        download(0x1A00, 0, 1u8),
+        download(0x1800, 2, 2u8),
+        download(0x1800, 6, 3u8),
+    ] {
+        let responses = process(&mut node, request);
+        assert_eq!(responses[0].data()[0], 0x60);
+    }
+    process(&mut node, nmt(NmtRequest::StartRemoteNode));
+
+    // counting starts with the SYNC whose counter equals the start value
+    let sent: Vec<usize> = (1..=8)
+        .map(|counter| {
+            let sync = CanOpenFrame::new(Node::<Data, 9>::SYNC_ID, &[counter]).unwrap();
+            process(&mut node, sync).len()
+        })
+        .collect();
+    assert_eq!(sent, [0, 0, 0, 1, 0, 1, 0, 1]);
+}
+
+#[test]
+fn test_emergency() {
+    let mut node = node();
+    let mut outbox = Vec::<CanOpenFrame>::new();
+    node.emergency(0x1000, 0x01, [0; 5], &mut outbox);
+    assert!(outbox.is_empty());
+
+    tick(&mut node, 0);
+    node.emergency(0x1000, 0x01, [0; 5], &mut outbox);
+    assert_eq!(outbox[0].id(), NODE_ID.emcy_cobid().into());
+    assert_eq!(outbox[0].data(), [0x00, 0x10, 0x01, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn test_apply_node_id_from_lss() {
+    const NEW_NODE_ID: NodeId = NodeId::NODE_ID_5;
+
+    let mut node = node();
+    node.lss = Some(Lss::new(
+        Some(NODE_ID),
+        Identity {
+            vendor_id: 1,
+            product_code: 2,
+            revision_number: 3,
+            serial_number: 4,
+        },
+    ));
+    tick(&mut node, 0);
+    process(&mut node, download(0x1A00, 1, 0x2000_0020u32));
+    process(&mut node, download(0x1A00, 0, 1u8));
+
+    // switch global configuration, configure node-ID, switch global waiting
+    for request in [
+        [0x04, 0x01, 0, 0, 0, 0, 0, 0],
+        [0x11, NEW_NODE_ID.raw(), 0, 0, 0, 0, 0, 0],
+        [0x04, 0x00, 0, 0, 0, 0, 0, 0],
+    ] {
+        process(
+            &mut node,
+            CanOpenFrame::new(Lss::LSS_REQUEST_ID, &request).unwrap(),
+        );
+    }
+    assert_eq!(node.pending_node_id(), Some(NEW_NODE_ID));
+    assert_eq!(node.nmt.node_id(), NODE_ID);
+
+    // the new node-ID is used after resetting the communication
+    let frames = process(&mut node, nmt(NmtRequest::ResetCommunication));
+    assert_eq!(frames[0].id(), NEW_NODE_ID.heartbeat_cobid().into());
+    assert_eq!(node.pending_node_id(), None);
+    assert_eq!(node.sdo_servers[0].rx_cobid, NEW_NODE_ID.sdo_rx_cobid());
+    assert_eq!(node.emcy.cob_id, NEW_NODE_ID.emcy_cobid());
+    assert_eq!(
+        node.od.data.tpdo.com.cob_id().id,
+        DefaultTPDO::TPDO1.cob_id(NEW_NODE_ID, false, false).id
+    );
+    assert_eq!(
+        node.od.data.rpdo.com.cob_id().id,
+        DefaultRPDO::RPDO1.cob_id(NEW_NODE_ID, false).id
+    );
+
+    // the old node-ID is not used anymore
+    assert!(process(&mut node, download(0x2000, 0, 1u32)).is_empty());
+    let request = CanOpenFrame::new(
+        NEW_NODE_ID.sdo_rx_cobid(),
+        &download_request(0x2000, 0, 1u32),
+    )
+    .unwrap();
+    let responses = process(&mut node, request);
+    assert_eq!(responses[0].id(), NEW_NODE_ID.sdo_tx_cobid().into());
+}
+
+#[test]
+fn test_restore_default_parameters() {
+    let mut node = node();
+    tick(&mut node, 0);
+    node.od.data.heartbeat_producer_time = 100;
+    node.od.data.value = 42;
+
+    // wrong signature
+    let responses = process(&mut node, download(0x1011, 1, 0x1234_5678u32));
+    assert_eq!(
+        responses[0].data(),
+        [0x80, 0x11, 0x10, 0x01, 0x20, 0x00, 0x00, 0x08]
+    );
+
+    // restore communication and manufacturer-specific parameters
+    process(&mut node, download(0x1011, 2, LOAD_SIGNATURE));
+    process(&mut node, download(0x1011, 4, LOAD_SIGNATURE));
+    assert_eq!(node.od.data.heartbeat_producer_time, 100);
+
+    // the defaults become valid with the next reset
+    process(&mut node, nmt(NmtRequest::ResetCommunication));
+    assert_eq!(node.od.data.heartbeat_producer_time, 1000);
+    assert_eq!(node.od.data.value, 42);
+
+    process(&mut node, nmt(NmtRequest::ResetNode));
+    assert_eq!(node.od.data.value, 7);
+
+    // requests are only executed once
+    node.od.data.value = 42;
+    process(&mut node, nmt(NmtRequest::ResetNode));
+    assert_eq!(node.od.data.value, 0);
+}
+
+#[test]
+fn test_restore_all_default_parameters() {
+    let mut node = node();
+    tick(&mut node, 0);
+    node.od.data.heartbeat_producer_time = 100;
+    node.od.data.value = 42;
+    process(&mut node, download(0x1011, 1, LOAD_SIGNATURE));
+
+    // each reset restores its own area
+    process(&mut node, nmt(NmtRequest::ResetCommunication));
+    assert_eq!(node.od.data.heartbeat_producer_time, 1000);
+    assert_eq!(node.od.data.value, 42);
+
+    node.od.data.heartbeat_producer_time = 100;
+    process(&mut node, nmt(NmtRequest::ResetNode));
+    assert_eq!(node.od.data.value, 7);
+    // reset node also resets the communication, which was restored already
+    assert_eq!(node.od.data.heartbeat_producer_time, 0);
+}
+
+#[test]
+fn test_store_parameters() {
+    let mut node = node();
+    let mut callback = Callback {
+        storage: Some(MemoryStorage::new()),
+        ..Default::default()
+    };
+    tick(&mut node, 0);
+    node.od.data.heartbeat_producer_time = 100;
+    node.od.data.value = 42;
+
+    // only manufacturer-specific parameters
+    let responses = process_with(
+        &mut node,
+        download(0x1010, 4, SAVE_SIGNATURE),
+        &mut callback,
+    );
+    assert_eq!(responses[0].data(), [0x60, 0x10, 0x10, 0x04, 0, 0, 0, 0]);
+    let storage = callback.storage.as_ref().unwrap();
+    assert_eq!(storage.get(0x2000, 0), Some(&42u32.to_le_bytes()[..]));
+    assert_eq!(storage.get(0x1017, 0), None);
+
+    process_with(
+        &mut node,
+        download(0x1010, 1, SAVE_SIGNATURE),
+        &mut callback,
+    );
+    let storage = callback.storage.as_ref().unwrap();
+    assert_eq!(storage.get(0x1017, 0), Some(&100u16.to_le_bytes()[..]));
+
+    // the saved values replace the ones set by the reset callbacks
+    node.od.data.value = 1;
+    process_with(&mut node, nmt(NmtRequest::ResetNode), &mut callback);
+    assert_eq!(node.od.data.heartbeat_producer_time, 100);
+    assert_eq!(node.od.data.value, 42);
+
+    // restoring the defaults removes the saved values
+    process_with(
+        &mut node,
+        download(0x1011, 4, LOAD_SIGNATURE),
+        &mut callback,
+    );
+    process_with(&mut node, nmt(NmtRequest::ResetNode), &mut callback);
+    assert_eq!(node.od.data.value, 7);
+    assert_eq!(node.od.data.heartbeat_producer_time, 100);
+    let storage = callback.storage.as_ref().unwrap();
+    assert_eq!(storage.get(0x2000, 0), None);
+}
+
+#[test]
+fn test_store_parameters_without_storage() {
+    let mut node = node();
+    tick(&mut node, 0);
+
+    let responses = process(&mut node, download(0x1010, 1, SAVE_SIGNATURE));
+    assert_eq!(
+        responses[0].data(),
+        [0x80, 0x10, 0x10, 0x01, 0x20, 0x00, 0x00, 0x08]
+    );
+    assert!(!node.od.data.store.is_pending(ParameterGroup::All));
+}
+
+#[test]
+fn test_load_parameters_at_power_on() {
+    let mut node = node();
+    let mut storage = MemoryStorage::new();
+    storage.save(0x2000, 0, &42u32.to_le_bytes()).unwrap();
+    storage.save(0x1017, 0, &100u16.to_le_bytes()).unwrap();
+    let mut callback = Callback {
+        storage: Some(storage),
+        ..Default::default()
+    };
+
+    let frames = tick_with(&mut node, 0, &mut callback);
+    assert_eq!(frames[0].data(), [0x00]);
+    assert_eq!(node.od.data.value, 42);
+    assert_eq!(node.od.data.heartbeat_producer_time, 100);
+
+    // only the first tick loads the parameters
+    node.od.data.value = 1;
+    tick_with(&mut node, 10, &mut callback);
+    assert_eq!(node.od.data.value, 1);
+    assert!(callback.parameter_errors.is_empty());
+}
+
+#[test]
+fn test_parameter_errors() {
+    let mut node = node();
+    let mut storage = MemoryStorage::new();
+    // too long for the u32 at 0x2000
+    storage.save(0x2000, 0, &[1, 2, 3, 4, 5]).unwrap();
+    let mut callback = Callback {
+        storage: Some(storage),
+        ..Default::default()
+    };
+
+    tick_with(&mut node, 0, &mut callback);
+    assert_eq!(node.od.data.value, 0x04030201);
+    assert_eq!(
+        callback.parameter_errors,
+        [(ParameterGroup::All, ODError::TooLong)]
+    );
+
+    // the communication parameters don't include 0x2000
+    callback.parameter_errors.clear();
+    process_with(
+        &mut node,
+        nmt(NmtRequest::ResetCommunication),
+        &mut callback,
+    );
+    assert!(callback.parameter_errors.is_empty());
+    process_with(&mut node, nmt(NmtRequest::ResetNode), &mut callback);
+    assert_eq!(
+        callback.parameter_errors,
+        [(ParameterGroup::All, ODError::TooLong)]
+    );
+}
@@ -0,0 +1,489 @@
+use canopen::objectdictionary::datalink::OdValue;
+use canopen::objectdictionary::integer::I48;
+use canopen::objectdictionary::object::{AccessType, DataType, Limits, ObjectFlags, PdoSize};
+use canopen::objectdictionary::od_cell::OdCell;
+use canopen::objectdictionary::{ODError, OdData};
+use canopen::storage::{MemoryStorage, ParameterGroup, ParameterStorage};
+
+#[derive(OdData)]
+struct Data {
+    #[canopen(index = 0x2000, default = 0xCAFE, persist)]
+    value: u32,
+    #[canopen(index = 0x2001, read_only, default = 1)]
+    status: u8,
+    #[canopen(index = 0x2002, write_only)]
+    command: i16,
+    #[canopen(index = 0x2003, default = true)]
+    enabled: bool,
+    #[canopen(index = 0x2004, persist)]
+    buffer: OdCell<[u8; 6]>,
+    #[canopen(index = 0x2005)]
+    counter: u64,
+    #[canopen(index = 0x2006)]
+    position: I48,
+    #[canopen(index = 0x2007, min = -10, max = 10, default = -1, persist)]
+    offset: i8,
+    #[canopen(index = 0x2008, min = 1)]
+    divider: u16,
+    #[canopen(index = 0x2009, min = -0.5, max = 0.5, default = 0.125)]
+    gain: f32,
+}
+
+fn data() -> Data {
+    Data {
+        value: 0,
+        status: 7,
+        command: 0,
+        enabled: false,
+        buffer: OdCell::new([0; 6]),
+        counter: 0,
+        position: I48::default(),
+        offset: 0,
+        divider: 1,
+        gain: 0.0,
+    }
+}
+
+#[test]
+fn test_get_and_set() {
+    let mut od = data().into_od();
+
+    od.set(0x2000, 0, 0x12345678u32).unwrap();
+    assert_eq!(od.data.value, 0x12345678);
+    assert_eq!(od.get::<u32>(0x2000, 0), Ok(0x12345678));
+
+    od.set(0x2002, 0, -5i16).unwrap();
+    assert_eq!(od.data.command, -5);
+
+    od.set(0x2003, 0, true).unwrap();
+    assert_eq!(od.get::<bool>(0x2003, 0), Ok(true));
+
+    assert_eq!(od.get::<u8>(0x2001, 0), Ok(7));
+
+    od.set(0x2005, 0, u64::MAX).unwrap();
+    assert_eq!(od.get::<u64>(0x2005, 0), Ok(u64::MAX));
+
+    od.set(0x2006, 0, I48::MIN).unwrap();
+    assert_eq!(
+        od.read(0x2006, 0).unwrap().as_bytes(),
+        [0, 0, 0, 0, 0, 0x80]
+    );
+    assert_eq!(od.get::<I48>(0x2006, 0), Ok(I48::MIN));
+}
+
+#[test]
+fn test_access_checks() {
+    let mut od = data().into_od();
+
+    assert_eq!(od.set(0x2001, 0, 1u8), Err(ODError::ReadOnlyError));
+    assert_eq!(od.data.status, 7);
+    assert_eq!(od.get::<i16>(0x2002, 0), Err(ODError::WriteOnlyError));
+
+    assert_eq!(od.set(0x2100, 0, 1u8), Err(ODError::ObjectDoesNotExist));
+    assert_eq!(od.get::<u32>(0x2000, 1), Err(ODError::SubindexDoesNotExist));
+}
+
+#[test]
+fn test_size_mismatch() {
+    let mut od = data().into_od();
+
+    assert_eq!(od.set(0x2000, 0, 1u16), Err(ODError::TooShort));
+    assert_eq!(od.get::<u16>(0x2000, 0), Err(ODError::TooLong));
+    assert_eq!(od.write(0x2003, 0, &[2]), Err(ODError::InvalidValue));
+}
+
+#[test]
+fn test_write_bytes() {
+    let mut od = data().into_od();
+
+    od.write(0x2004, 0, &[1, 2, 3, 4, 5, 6]).unwrap();
+    assert_eq!(od.data.buffer.get(), &[1, 2, 3, 4, 5, 6]);
+    assert!(!od.data.buffer.is_locked());
+
+    assert_eq!(od.write(0x2004, 0, &[0; 7]), Err(ODError::TooLong));
+}
+
+#[test]
+fn test_limits() {
+    let mut od = data().into_od();
+
+    od.set(0x2007, 0, -10i8).unwrap();
+    od.set(0x2007, 0, 10i8).unwrap();
+    assert_eq!(od.set(0x2007, 0, -11i8), Err(ODError::ValueTooLow));
+    assert_eq!(od.set(0x2007, 0, 11i8), Err(ODError::ValueTooHigh));
+    assert_eq!(od.data.offset, 10);
+
+    assert_eq!(od.set(0x2008, 0, 0u16), Err(ODError::ValueTooLow));
+    od.set(0x2008, 0, u16::MAX).unwrap();
+
+    od.set(0x2009, 0, 0.25f32).unwrap();
+    assert_eq!(od.set(0x2009, 0, 0.75f32), Err(ODError::ValueTooHigh));
+    assert_eq!(od.set(0x2009, 0, f32::NAN), Err(ODError::InvalidValue));
+    assert_eq!(od.data.gain, 0.25);
+
+    // the size is checked before the value
+    assert_eq!(od.write(0x2008, 0, &[0]), Err(ODError::TooShort));
+
+    assert_eq!(
+        od.limits(0x2007, 0),
+        Ok(Some(Limits::Signed { min: -10, max: 10 }))
+    );
+    assert_eq!(
+        od.limits(0x2008, 0),
+        Ok(Some(Limits::Unsigned {
+            min: 1,
+            max: u16::MAX as u64
+        }))
+    );
+    assert_eq!(od.limits(0x2000, 0), Ok(None));
+}
+
+#[test]
+fn test_restore_defaults() {
+    let mut od = data().into_od();
+    assert_eq!(
+        od.default_value(0x2000, 0),
+        Ok(Some(&[0xFE, 0xCA, 0, 0][..]))
+    );
+    assert_eq!(od.default_value(0x2002, 0), Ok(None));
+
+    // communication parameters don't include 0x2000 to 0x5FFF
+    od.restore_defaults(ParameterGroup::Communication).unwrap();
+    assert_eq!(od.data.value, 0);
+
+    od.data.command = 5;
+    od.restore_defaults(ParameterGroup::Manufacturer).unwrap();
+    assert_eq!(od.data.value, 0xCAFE);
+    assert_eq!(od.data.status, 1);
+    assert!(od.data.enabled);
+    assert_eq!(od.data.offset, -1);
+    assert_eq!(od.data.gain, 0.125);
+    // objects without a default are unchanged
+    assert_eq!(od.data.command, 5);
+}
+
+#[test]
+fn test_store_and_load_parameters() {
+    let mut od = data().into_od();
+    let mut storage = MemoryStorage::new();
+    od.data.value = 5;
+    od.data.buffer = OdCell::new([1, 2, 3, 4, 5, 6]);
+    od.data.status = 3;
+
+    od.store_parameters(&mut storage, ParameterGroup::Application)
+        .unwrap();
+    assert!(storage.is_empty());
+    od.store_parameters(&mut storage, ParameterGroup::All)
+        .unwrap();
+    assert_eq!(storage.get(0x2000, 0), Some(&[5, 0, 0, 0][..]));
+    assert_eq!(storage.get(0x2004, 0), Some(&[1, 2, 3, 4, 5, 6][..]));
+    // not persistent
+    assert_eq!(storage.get(0x2001, 0), None);
+
+    let mut od = data().into_od();
+    od.load_parameters(&mut storage, ParameterGroup::Manufacturer)
+        .unwrap();
+    assert_eq!(od.data.value, 5);
+    assert_eq!(od.data.buffer.get(), &[1, 2, 3, 4, 5, 6]);
+    assert_eq!(od.data.status, 7);
+
+    // saved values are checked like any other write
+    storage.save(0x2007, 0, &[100]).unwrap();
+    storage.save(0x2000, 0, &[6, 0, 0, 0]).unwrap();
+    assert_eq!(
+        od.load_parameters(&mut storage, ParameterGroup::All),
+        Err(ODError::ValueTooHigh)
+    );
+    assert_eq!(od.data.offset, 0);
+    assert_eq!(od.data.value, 6);
+
+    od.clear_parameters(&mut storage, ParameterGroup::All)
+        .unwrap();
+    assert!(storage.is_empty());
+}
+
+#[test]
+fn test_entries() {
+    let od = data().into_od();
+    assert_eq!(od.len(), 10);
+    let entries: Vec<_> = od.entries().collect();
+    assert!(entries
+        .windows(2)
+        .all(|pair| pair[0].info.index < pair[1].info.index));
+    assert_eq!(entries[1].info.index, 0x2001);
+    assert!(entries[1].info.flags.is_read_only());
+    assert_eq!(entries[7].limits, od.limits(0x2007, 0).unwrap());
+
+    let entry = od.entry(0x2000, 0).unwrap();
+    assert!(entry.info.flags.is_persistent());
+    assert_eq!(entry.default, Some(&[0xFE, 0xCA, 0, 0][..]));
+    assert!(od.entry(0x2000, 1).is_err());
+}
+
+#[test]
+fn test_metadata() {
+    let od = data().into_od();
+
+    let entry = od.entry(0x2006, 0).unwrap();
+    assert_eq!(entry.name, "position");
+    assert_eq!(entry.data_type, Some(DataType::INTEGER48));
+    assert_eq!(entry.info.flags.access_type(), AccessType::ReadWrite);
+
+    let entry = od.entry(0x2002, 0).unwrap();
+    assert_eq!(entry.data_type, Some(DataType::INTEGER16));
+    assert_eq!(entry.info.flags.access_type(), AccessType::WriteOnly);
+
+    #[derive(OdData)]
+    struct Named {
+        #[canopen(index = 0x2100, subindex = 0, read_only)]
+        count: u8,
+        #[canopen(record = "Motor", index = 0x2100)]
+        #[canopen(index = 0x2100, subindex = 1, name = "Speed")]
+        speed: u16,
+        #[canopen(index = 0x2100, subindex = 2)]
+        raw: u32,
+    }
+
+    let od = Named {
+        count: 2,
+        speed: 0,
+        raw: 0,
+    }
+    .into_od();
+    let entries: Vec<_> = od
+        .entries()
+        .map(|entry| (entry.name, entry.data_type))
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            ("Motor", Some(DataType::UNSIGNED8)),
+            ("Speed", Some(DataType::UNSIGNED16)),
+            ("Motor", Some(DataType::UNSIGNED32))
+        ]
+    );
+}
+
+#[test]
+fn test_report() {
+    let mut od = data().into_od();
+    od.data.value = 0x04030201;
+    let mut report = String::new();
+    od.report(&mut report).unwrap();
+    let lines: Vec<_> = report.lines().collect();
+    assert_eq!(lines.len(), 10);
+    assert_eq!(lines[0], "0x2000sub0 value rw 4 bytes: 01 02 03 04");
+    assert_eq!(lines[1], "0x2001sub0 status ro 1 bytes: 07");
+    assert_eq!(lines[2], "0x2002sub0 command wo");
+    assert_eq!(lines[4], "0x2004sub0 buffer rw 6 bytes: 00 00 00 00 00 00");
+
+    #[derive(OdData)]
+    struct Large {
+        #[canopen(index = 0x2000)]
+        bytes: OdCell<[u8; 20]>,
+    }
+
+    let mut od = Large {
+        bytes: OdCell::new([0xAA; 20]),
+    }
+    .into_od();
+    let mut report = String::new();
+    od.report(&mut report).unwrap();
+    assert_eq!(
+        report,
+        format!("0x2000sub0 bytes rw 20 bytes:{} ...\n", " AA".repeat(16))
+    );
+    // the partial read doesn't keep the value locked
+    assert!(od.data.bytes.try_get_mut().is_some());
+}
+
+#[test]
+fn test_take_changed() {
+    let mut od = data().into_od();
+    assert!(od.take_changed().is_none());
+
+    od.set(0x2002, 0, 1i16).unwrap();
+    od.set(0x2000, 0, 1u32).unwrap();
+    od.set(0x2000, 0, 2u32).unwrap();
+    // failed writes don't count
+    assert!(od.set(0x2007, 0, 100i8).is_err());
+
+    let info = od.take_changed().unwrap();
+    assert_eq!((info.index, info.subindex), (0x2000, 0));
+    assert_eq!(od.take_changed().unwrap().index, 0x2002);
+    assert!(od.take_changed().is_none());
+
+    od.set(0x2000, 0, 3u32).unwrap();
+    od.clear_changed();
+    assert!(od.take_changed().is_none());
+}
+
+#[derive(OdData)]
+#[canopen(record)]
+struct Motor {
+    #[canopen(read_only)]
+    status: u16,
+    #[canopen(min = 1, max = 100, default = 50, persist)]
+    speed: u8,
+    #[canopen(subindex = 5, validate = check_mode)]
+    mode: i8,
+    torque: u32,
+}
+
+fn check_mode(_: &Motor, mode: i8) -> Result<(), ODError> {
+    if mode < 0 {
+        return Err(ODError::InvalidValue);
+    }
+    Ok(())
+}
+
+#[derive(OdData)]
+struct Drive {
+    #[canopen(index = 0x2000, validate = check_before)]
+    before: u8,
+    #[canopen(record = "Motor", index = 0x2100)]
+    motor: Motor,
+    #[canopen(index = 0x2200, min = 0, max = 10, default = 7)]
+    after: u8,
+}
+
+fn check_before(_: &Drive, value: u8) -> Result<(), ODError> {
+    if value == 0xFF {
+        return Err(ODError::InvalidValue);
+    }
+    Ok(())
+}
+
+fn drive() -> Drive {
+    Drive {
+        before: 0,
+        motor: Motor {
+            status: 3,
+            speed: 10,
+            mode: 0,
+            torque: 0,
+        },
+        after: 0,
+    }
+}
+
+#[test]
+fn test_nested_record() {
+    let mut od = drive().into_od();
+    assert_eq!(od.len(), 7);
+    assert_eq!(od.get::<u8>(0x2100, 0), Ok(6));
+    assert_eq!(od.set(0x2100, 0, 1u8), Err(ODError::ReadOnlyError));
+    assert_eq!(od.get::<u16>(0x2100, 1), Ok(3));
+    assert_eq!(od.set(0x2100, 1, 1u16), Err(ODError::ReadOnlyError));
+    assert_eq!(od.get::<u8>(0x2100, 3), Err(ODError::SubindexDoesNotExist));
+
+    od.set(0x2100, 6, 0x12345678u32).unwrap();
+    assert_eq!(od.data.motor.torque, 0x12345678);
+    od.set(0x2000, 0, 1u8).unwrap();
+    od.set(0x2200, 0, 2u8).unwrap();
+    assert_eq!((od.data.before, od.data.after), (1, 2));
+
+    // attributes of the record fields
+    assert_eq!(od.set(0x2100, 2, 0u8), Err(ODError::ValueTooLow));
+    assert_eq!(od.set(0x2100, 5, -1i8), Err(ODError::InvalidValue));
+    od.set(0x2100, 5, 1i8).unwrap();
+    assert_eq!(od.set(0x2000, 0, 0xFFu8), Err(ODError::InvalidValue));
+    assert_eq!(od.set(0x2200, 0, 11u8), Err(ODError::ValueTooHigh));
+    assert_eq!(
+        od.limits(0x2100, 2),
+        Ok(Some(Limits::Unsigned { min: 1, max: 100 }))
+    );
+    assert_eq!(od.default_value(0x2100, 2), Ok(Some(&[50][..])));
+    od.restore_defaults(ParameterGroup::All).unwrap();
+    assert_eq!((od.data.motor.speed, od.data.after), (50, 7));
+}
+
+#[test]
+fn test_nested_record_entries() {
+    let od = drive().into_od();
+    let entries: Vec<_> = od
+        .entries()
+        .map(|entry| (entry.info.index, entry.info.subindex, entry.name))
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            (0x2000, 0, "before"),
+            (0x2100, 0, "Highest sub-index supported"),
+            (0x2100, 1, "status"),
+            (0x2100, 2, "speed"),
+            (0x2100, 5, "mode"),
+            (0x2100, 6, "torque"),
+            (0x2200, 0, "after"),
+        ]
+    );
+
+    let entry = od.entry(0x2100, 0).unwrap();
+    assert_eq!(entry.data_type, Some(DataType::UNSIGNED8));
+    assert!(entry.info.flags.is_read_only());
+    let entry = od.entry(0x2100, 2).unwrap();
+    assert!(entry.info.flags.is_persistent());
+    assert_eq!(entry.info.od_position.0, 3);
+    assert_eq!(
+        od.entry(0x2100, 6).unwrap().data_type,
+        Some(DataType::UNSIGNED32)
+    );
+}
+
+#[derive(OdValue, Clone, Copy, PartialEq, Debug)]
+#[repr(u16)]
+enum State {
+    Off = 0,
+    On = 0x100,
+    Fault = 0xFFFF,
+}
+
+#[derive(OdValue, Clone, Copy, PartialEq, Debug)]
+#[canopen(reserved = 0xF0)]
+struct Flags(u8);
+
+#[test]
+fn test_enum_and_flags() {
+    #[derive(OdData)]
+    struct Values {
+        #[canopen(index = 0x2000, typ = u16)]
+        state: State,
+        #[canopen(index = 0x2001, typ = UNSIGNED8)]
+        flags: Flags,
+    }
+
+    let mut od = Values {
+        state: State::Off,
+        flags: Flags(0),
+    }
+    .into_od();
+    od.write(0x2000, 0, &[0, 1]).unwrap();
+    assert_eq!(od.data.state, State::On);
+    assert_eq!(od.write(0x2000, 0, &[1, 0]), Err(ODError::InvalidValue));
+    assert_eq!(od.write(0x2000, 0, &[0]), Err(ODError::TooShort));
+    od.set(0x2000, 0, State::Fault).unwrap();
+    assert_eq!(od.read(0x2000, 0).unwrap().as_bytes(), [0xFF, 0xFF]);
+    assert_eq!(od.get::<State>(0x2000, 0), Ok(State::Fault));
+
+    od.set(0x2001, 0, Flags(0x0F)).unwrap();
+    assert_eq!(od.set(0x2001, 0, Flags(0x10)), Err(ODError::InvalidValue));
+    assert_eq!(od.write(0x2001, 0, &[0x80]), Err(ODError::InvalidValue));
+    assert_eq!(od.get::<u8>(0x2001, 0), Ok(0x0F));
+
+    // `typ` makes them mappable into PDOs
+    let entry = od.entry(0x2000, 0).unwrap();
+    assert_eq!(entry.data_type, Some(DataType::UNSIGNED16));
+    assert!(entry.info.flags.pdo_size().is_some());
+}
+
+#[test]
+fn test_pdo_size_flags() {
+    let flags = ObjectFlags::empty().set_read_only();
+    assert_eq!(flags.pdo_size(), None);
+    let flags = flags.set_pdo_size(PdoSize::Three);
+    assert_eq!(flags.pdo_size(), Some(PdoSize::Three));
+    // replaces the previous size instead of combining the bits
+    let flags = flags.set_pdo_size(PdoSize::Four);
+    assert_eq!(flags.pdo_size(), Some(PdoSize::Four));
+    assert!(flags.is_read_only());
+}
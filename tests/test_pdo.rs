@@ -1,6 +1,13 @@
-use canopen::objectdictionary::OdData;
-use canopen::pdo::{DefaultTPDO, TPDO};
+use embedded_can::Frame;
+
+use canopen::objectdictionary::integer::I24;
+use canopen::objectdictionary::object::{ObjectFlags, ObjectInfo, PdoSize};
+use canopen::objectdictionary::{ODError, OdData, OdPosition};
+use canopen::pdo::{pack_object_data, DefaultTPDO, TPDOMappingParameters, TPDO};
 use canopen::NodeId;
+use frame::CanOpenFrame;
+
+mod frame;
 
 #[derive(OdData)]
 struct Data {
@@ -33,3 +40,99 @@ fn tpdo() {
     dbg!(od.read(0x1A00, 0x00).unwrap().as_bytes());
     dbg!(od.read(0x1A00, 0x01).unwrap().as_bytes());
 }
+
+#[test]
+fn tpdo_with_large_and_odd_sized_objects() {
+    #[derive(OdData)]
+    struct Data {
+        #[canopen(index = 0x1800, subindex = 0x01)]
+        #[canopen(index = 0x1A00, subindex = 0x00)]
+        #[canopen(index = 0x1A00, subindex = 0x01)]
+        #[canopen(index = 0x1A00, subindex = 0x02)]
+        tpdo: TPDO,
+        #[canopen(index = 0x2000)]
+        counter: u64,
+        #[canopen(index = 0x2001)]
+        position: I24,
+        #[canopen(index = 0x2002)]
+        speed: i32,
+    }
+
+    let mut od = Data {
+        tpdo: DefaultTPDO::TPDO1.new(NodeId::NODE_ID_1, |_, new| Ok(new)),
+        counter: 0x0807_0605_0403_0201,
+        position: I24::new(-2).unwrap(),
+        speed: 0x0D0C_0B0A,
+    }
+    .into_od();
+    let cob_id = od.data.tpdo.com.cob_id().id;
+
+    od.set(0x1A00, 1, pack_object_data(0x2000, 0, 64)).unwrap();
+    od.set(0x1A00, 0, 1u8).unwrap();
+    let map = od.data.tpdo.map.clone();
+    let frame: CanOpenFrame = map.create_frame(cob_id, &mut od).unwrap();
+    assert_eq!(frame.data(), [1, 2, 3, 4, 5, 6, 7, 8]);
+
+    od.set(0x1A00, 0, 0u8).unwrap();
+    od.set(0x1A00, 1, pack_object_data(0x2001, 0, 24)).unwrap();
+    od.set(0x1A00, 2, pack_object_data(0x2002, 0, 32)).unwrap();
+    od.set(0x1A00, 0, 2u8).unwrap();
+    let map = od.data.tpdo.map.clone();
+    let frame: CanOpenFrame = map.create_frame(cob_id, &mut od).unwrap();
+    assert_eq!(frame.data(), [0xFE, 0xFF, 0xFF, 0x0A, 0x0B, 0x0C, 0x0D]);
+
+    // the mapped objects must fit into 8 bytes
+    od.set(0x1A00, 0, 0u8).unwrap();
+    od.set(0x1A00, 2, pack_object_data(0x2000, 0, 64)).unwrap();
+    assert_eq!(od.set(0x1A00, 0, 2u8), Err(ODError::PDOOverflow));
+
+    // the length must match the object
+    assert_eq!(
+        od.set(0x1A00, 1, pack_object_data(0x2001, 0, 32)),
+        Err(ODError::ObjectCannotBeMapped)
+    );
+}
+
+#[test]
+fn tpdo_reset_restores_power_on_mapping() {
+    #[derive(OdData)]
+    struct Data {
+        #[canopen(index = 0x1800, subindex = 0x01)]
+        #[canopen(index = 0x1A00, subindex = 0x00)]
+        #[canopen(index = 0x1A00, subindex = 0x01)]
+        tpdo: TPDO,
+        #[canopen(index = 0x2000)]
+        speed: i32,
+        #[canopen(index = 0x2001)]
+        counter: u16,
+    }
+
+    // pre-defined mapping of the device
+    let mut map = TPDOMappingParameters::default();
+    let info = ObjectInfo {
+        index: 0x2000,
+        subindex: 0,
+        flags: ObjectFlags::empty().set_pdo_size(PdoSize::Four),
+        od_position: OdPosition(3),
+    };
+    map.map_object(0, info, 32).unwrap();
+    map.set_num_mapped_objects(1).unwrap();
+    let com = DefaultTPDO::TPDO1
+        .new(NodeId::NODE_ID_1, |_, new| Ok(new))
+        .com;
+
+    let mut od = Data {
+        tpdo: TPDO::new(com, map),
+        speed: 0,
+        counter: 0,
+    }
+    .into_od();
+    od.set(0x1A00, 0, 0u8).unwrap();
+    od.set(0x1A00, 1, pack_object_data(0x2001, 0, 16)).unwrap();
+    od.set(0x1A00, 0, 1u8).unwrap();
+    assert_eq!(od.get::<u32>(0x1A00, 1), Ok(0x2001_0010));
+
+    od.data.tpdo.reset();
+    assert_eq!(od.get::<u8>(0x1A00, 0), Ok(1));
+    assert_eq!(od.get::<u32>(0x1A00, 1), Ok(0x2000_0020));
+}
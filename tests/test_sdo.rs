@@ -1,9 +1,14 @@
+use core::time::Duration;
+
 use embedded_can::Frame;
 
+use canopen::objectdictionary::datalink::{DomainData, WriteStream};
+use canopen::objectdictionary::domain::Domain;
+use canopen::objectdictionary::integer::{I24, U40};
 use canopen::objectdictionary::od_cell::OdCell;
-use canopen::objectdictionary::OdData;
-use canopen::sdo::client::{ReadInto, ReadResult, SdoClient};
-use canopen::sdo::SdoServer;
+use canopen::objectdictionary::{ODError, OdData, OdInfo};
+use canopen::sdo::client::{ProtocolError, ReadInto, ReadResult, SdoClient, SdoValue};
+use canopen::sdo::{SDOAbortCode, SdoServer};
 use canopen::{NodeId, ObjectDictionary};
 use frame::CanOpenFrame;
 
@@ -112,6 +117,41 @@ fn test_segmented_download() {
     assert_eq!(od.data.obj.get().as_slice(), b"A long string");
 }
 
+#[test]
+fn test_segmented_download_marks_changed() {
+    #[derive(OdData)]
+    struct Data {
+        #[canopen(index = 1)]
+        obj: OdCell<[u8; 13]>,
+    }
+
+    let mut od = Data {
+        obj: OdCell::new([0; 13]),
+    }
+    .into_od();
+    let mut sdo_server = SdoServer::new(NODE_ID);
+
+    on_sdo_message!(
+        sdo_server,
+        od,
+        [0x21, 0x01, 0x00, 0x00, 0x0d, 0x00, 0x00, 0x00]
+    );
+    on_sdo_message!(
+        sdo_server,
+        od,
+        [0x00, 0x41, 0x20, 0x6c, 0x6f, 0x6e, 0x67, 0x20]
+    );
+    // the object only counts as changed after the last segment
+    assert!(od.take_changed().is_none());
+    on_sdo_message!(
+        sdo_server,
+        od,
+        [0x13, 0x73, 0x74, 0x72, 0x69, 0x6e, 0x67, 0x00]
+    );
+    assert_eq!(od.take_changed().map(|info| info.index), Some(1));
+    assert!(od.take_changed().is_none());
+}
+
 fn read<B: ReadInto, OD, const N: usize>(
     index: u16,
     subindex: u8,
@@ -235,3 +275,631 @@ fn test_abort() {
         [0x80, 0x01, 0x00, 0x01, 0x11, 0x00, 0x09, 0x06]
     );
 }
+
+fn write<OD, const N: usize>(
+    index: u16,
+    subindex: u8,
+    server: &mut SdoServer,
+    od: &mut ObjectDictionary<OD, N>,
+    data: &[u8],
+) -> Result<(), ProtocolError> {
+    let sdo_client = SdoClient::new(NODE_ID);
+    let mut sdo_writer = sdo_client.write(index, subindex, data);
+
+    loop {
+        match sdo_writer.poll()? {
+            ReadResult::NextRequest(message) => {
+                let response: CanOpenFrame = server.on_message(&message.into_frame(), od).unwrap();
+                sdo_client.on_message(&response);
+            }
+            ReadResult::Done => return Ok(()),
+            ReadResult::Waiting => unreachable!(),
+        }
+    }
+}
+
+#[test]
+fn test_large_and_odd_sized_values() {
+    #[derive(OdData)]
+    struct Data {
+        #[canopen(index = 1)]
+        unsigned64: u64,
+        #[canopen(index = 2)]
+        real64: f64,
+        #[canopen(index = 3)]
+        integer24: I24,
+        #[canopen(index = 4)]
+        unsigned40: U40,
+    }
+
+    let mut od = Data {
+        unsigned64: 0,
+        real64: 0.0,
+        integer24: I24::default(),
+        unsigned40: U40::default(),
+    }
+    .into_od();
+    let mut sdo_server = SdoServer::new(NODE_ID);
+
+    // segmented downloads
+    write(
+        1,
+        0,
+        &mut sdo_server,
+        &mut od,
+        &0x0102_0304_0506_0708u64.to_bytes(),
+    )
+    .unwrap();
+    assert_eq!(od.data.unsigned64, 0x0102_0304_0506_0708);
+    write(2, 0, &mut sdo_server, &mut od, &1.5f64.to_bytes()).unwrap();
+    assert_eq!(od.data.real64, 1.5);
+    let value = U40::new(0xFF_0000_0001).unwrap();
+    write(4, 0, &mut sdo_server, &mut od, &value.to_bytes()).unwrap();
+    assert_eq!(od.data.unsigned40, value);
+
+    // expedited download
+    let value = I24::new(-100_000).unwrap();
+    write(3, 0, &mut sdo_server, &mut od, &value.to_bytes()).unwrap();
+    assert_eq!(od.data.integer24, value);
+
+    // wrong size
+    assert_eq!(
+        write(1, 0, &mut sdo_server, &mut od, &[0; 7]),
+        Err(ProtocolError::Abort(SDOAbortCode::TooShort))
+    );
+    assert_eq!(
+        write(3, 0, &mut sdo_server, &mut od, &[0; 4]),
+        Err(ProtocolError::Abort(SDOAbortCode::TooLong))
+    );
+
+    // uploads
+    let mut data = 0u64;
+    read(1, 0, &mut sdo_server, &mut od, &mut data);
+    assert_eq!(data, 0x0102_0304_0506_0708);
+    let mut data = I24::default();
+    read(3, 0, &mut sdo_server, &mut od, &mut data);
+    assert_eq!(data.get(), -100_000);
+    let mut data = U40::default();
+    read(4, 0, &mut sdo_server, &mut od, &mut data);
+    assert_eq!(data.get(), 0xFF_0000_0001);
+}
+
+#[test]
+fn test_segmented_download_without_size() {
+    #[derive(OdData)]
+    struct Data {
+        #[canopen(index = 1)]
+        obj: u64,
+    }
+
+    let mut od = Data { obj: 0 }.into_od();
+    let mut sdo_server = SdoServer::new(NODE_ID);
+
+    // REQUEST_DOWNLOAD without SIZE_SPECIFIED
+    let response = on_sdo_message!(sdo_server, od, [0x20, 0x01, 0x00, 0x00, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x60, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+    );
+    on_sdo_message!(sdo_server, od, [0x00, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(od.data.obj, 0);
+    // REQUEST_SEGMENT_DOWNLOAD|TOGGLE_BIT|NO_MORE_DATA|unused_bytes=6
+    let response = on_sdo_message!(sdo_server, od, [0x1d, 8, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(od.data.obj, 0x0807_0605_0403_0201);
+
+    // the length is checked with the last segment
+    on_sdo_message!(sdo_server, od, [0x20, 0x01, 0x00, 0x00, 0, 0, 0, 0]);
+    let response = on_sdo_message!(sdo_server, od, [0x01, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x80, 0x01, 0x00, 0x00, 0x13, 0x00, 0x07, 0x06]
+    );
+    on_sdo_message!(sdo_server, od, [0x20, 0x01, 0x00, 0x00, 0, 0, 0, 0]);
+    on_sdo_message!(sdo_server, od, [0x00, 1, 2, 3, 4, 5, 6, 7]);
+    let response = on_sdo_message!(sdo_server, od, [0x11, 8, 9, 0, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x80, 0x01, 0x00, 0x00, 0x12, 0x00, 0x07, 0x06]
+    );
+    assert_eq!(od.data.obj, 0x0807_0605_0403_0201);
+}
+
+#[test]
+fn test_variable_length_strings() {
+    #[derive(OdData)]
+    struct Data {
+        #[canopen(index = 1)]
+        name: OdCell<heapless::String<20>>,
+        #[canopen(index = 2)]
+        bytes: OdCell<heapless::Vec<u8, 10>>,
+    }
+
+    let mut od = Data {
+        name: OdCell::new(heapless::String::new()),
+        bytes: OdCell::new(heapless::Vec::new()),
+    }
+    .into_od();
+    let mut sdo_server = SdoServer::new(NODE_ID);
+
+    write(1, 0, &mut sdo_server, &mut od, b"A long string").unwrap();
+    assert_eq!(od.data.name.get(), "A long string");
+
+    // only the written bytes are kept and uploaded
+    write(1, 0, &mut sdo_server, &mut od, b"short").unwrap();
+    assert_eq!(od.data.name.get(), "short");
+    let mut data = Vec::new();
+    read(1, 0, &mut sdo_server, &mut od, &mut data);
+    assert_eq!(data, b"short");
+
+    write(1, 0, &mut sdo_server, &mut od, b"abc").unwrap();
+    assert_eq!(od.data.name.get(), "abc");
+
+    assert_eq!(
+        write(1, 0, &mut sdo_server, &mut od, &[b'x'; 21]),
+        Err(ProtocolError::Abort(SDOAbortCode::TooLong))
+    );
+    assert_eq!(
+        write(1, 0, &mut sdo_server, &mut od, "ünicode".as_bytes()),
+        Err(ProtocolError::Abort(SDOAbortCode::InvalidValue))
+    );
+
+    write(
+        2,
+        0,
+        &mut sdo_server,
+        &mut od,
+        &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10],
+    )
+    .unwrap();
+    assert_eq!(od.data.bytes.get(), &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+    assert_eq!(
+        write(2, 0, &mut sdo_server, &mut od, &[0; 11]),
+        Err(ProtocolError::Abort(SDOAbortCode::TooLong))
+    );
+    assert_eq!(od.data.bytes.get(), &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+
+    write(2, 0, &mut sdo_server, &mut od, &[]).unwrap();
+    let mut data = Vec::new();
+    read(2, 0, &mut sdo_server, &mut od, &mut data);
+    assert_eq!(data, []);
+}
+
+/// Generates `size` bytes on the fly and checks downloaded data against the same pattern
+struct Pattern {
+    size: usize,
+    size_known: bool,
+    bytes_written: usize,
+    segments: usize,
+}
+
+impl Pattern {
+    fn new(size: usize, size_known: bool) -> Self {
+        Pattern {
+            size,
+            size_known,
+            bytes_written: 0,
+            segments: 0,
+        }
+    }
+
+    fn byte(offset: usize) -> u8 {
+        (offset % 251) as u8
+    }
+}
+
+impl DomainData for Pattern {
+    fn size(&self, _: u16, _: u8) -> Option<usize> {
+        self.size_known.then_some(self.size)
+    }
+
+    fn read(&mut self, _: u16, _: u8, offset: usize, buf: &mut [u8]) -> Result<usize, ODError> {
+        let len = buf.len().min(self.size - offset);
+        for (i, byte) in buf[..len].iter_mut().enumerate() {
+            *byte = Pattern::byte(offset + i);
+        }
+        Ok(len)
+    }
+
+    fn write(&mut self, data: WriteStream, _: OdInfo) -> Result<(), ODError> {
+        if data.promised_size.is_some_and(|size| size > 2048) {
+            return Err(ODError::OutOfMemory);
+        }
+        if data.offset != self.bytes_written {
+            return Err(ODError::LocalControlError);
+        }
+        for (i, byte) in data.new_data.iter().enumerate() {
+            if *byte != Pattern::byte(data.offset + i) {
+                return Err(ODError::InvalidValue);
+            }
+        }
+        self.bytes_written += data.new_data.len();
+        self.segments += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_domain() {
+    #[derive(OdData)]
+    struct Data {
+        #[canopen(index = 1)]
+        known_size: Domain<Pattern>,
+        #[canopen(index = 2)]
+        unknown_size: Domain<Pattern>,
+        #[canopen(index = 3)]
+        small: Domain<Pattern>,
+    }
+
+    let mut od = Data {
+        known_size: Domain::new(Pattern::new(1000, true)),
+        // a multiple of the segment size ends with an empty segment
+        unknown_size: Domain::new(Pattern::new(700, false)),
+        small: Domain::new(Pattern::new(3, false)),
+    }
+    .into_od();
+    let mut sdo_server = SdoServer::new(NODE_ID);
+    let expected = |size| (0..size).map(Pattern::byte).collect::<Vec<_>>();
+
+    let mut data = Vec::new();
+    read(1, 0, &mut sdo_server, &mut od, &mut data);
+    assert_eq!(data, expected(1000));
+    let mut data = Vec::new();
+    read(2, 0, &mut sdo_server, &mut od, &mut data);
+    assert_eq!(data, expected(700));
+    let mut data = Vec::new();
+    read(3, 0, &mut sdo_server, &mut od, &mut data);
+    assert_eq!(data, expected(3));
+
+    write(1, 0, &mut sdo_server, &mut od, &expected(500)).unwrap();
+    assert_eq!(od.data.known_size.get().bytes_written, 500);
+    // one empty segment to start with, then 7 bytes per segment
+    assert_eq!(od.data.known_size.get().segments, 1 + 500usize.div_ceil(7));
+
+    assert_eq!(
+        write(2, 0, &mut sdo_server, &mut od, &[0; 4096]),
+        Err(ProtocolError::Abort(SDOAbortCode::OutOfMemory))
+    );
+    assert_eq!(od.read(1, 0).err(), Some(ODError::UnsupportedAccess));
+}
+
+#[test]
+fn test_consistent_segmented_upload() {
+    #[derive(OdData)]
+    struct Data {
+        #[canopen(index = 1)]
+        obj: OdCell<[u8; 9]>,
+        #[canopen(index = 2)]
+        text: OdCell<heapless::String<16>>,
+    }
+
+    let mut od = Data {
+        obj: OdCell::new([1, 2, 3, 4, 5, 6, 7, 8, 9]),
+        text: OdCell::new("abcdefghi".try_into().unwrap()),
+    }
+    .into_od();
+    let mut sdo_server = SdoServer::new(NODE_ID);
+
+    // upload the first segment
+    on_sdo_message!(sdo_server, od, [0x40, 0x01, 0x00, 0x00, 0, 0, 0, 0]);
+    let response = on_sdo_message!(sdo_server, od, [0x60, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(response.unwrap().data(), [0x00, 1, 2, 3, 4, 5, 6, 7]);
+
+    // the application can't change the value during the upload
+    assert!(od.data.obj.is_read_locked());
+    assert!(od.data.obj.try_get_mut().is_none());
+
+    let response = on_sdo_message!(sdo_server, od, [0x70, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(response.unwrap().data(), [0x1b, 8, 9, 0, 0, 0, 0, 0]);
+    assert!(!od.data.obj.is_read_locked());
+    od.data.obj.try_get_mut().unwrap()[0] = 10;
+
+    // forcing a change aborts the upload
+    on_sdo_message!(sdo_server, od, [0x40, 0x01, 0x00, 0x00, 0, 0, 0, 0]);
+    on_sdo_message!(sdo_server, od, [0x60, 0, 0, 0, 0, 0, 0, 0]);
+    od.data.obj.get_mut()[8] = 0;
+    let response = on_sdo_message!(sdo_server, od, [0x70, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x80, 0x01, 0x00, 0x00, 0x21, 0x00, 0x00, 0x08]
+    );
+
+    // an aborted upload releases the lock
+    on_sdo_message!(sdo_server, od, [0x40, 0x01, 0x00, 0x00, 0, 0, 0, 0]);
+    on_sdo_message!(sdo_server, od, [0x60, 0, 0, 0, 0, 0, 0, 0]);
+    assert!(od.data.obj.is_read_locked());
+    on_sdo_message!(sdo_server, od, [0x80, 0x01, 0x00, 0x00, 0, 0, 0, 0x08]);
+    assert!(!od.data.obj.is_read_locked());
+
+    // the value is locked with the indicated size, before the first segment
+    let response = on_sdo_message!(sdo_server, od, [0x40, 0x02, 0x00, 0x00, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x41, 0x02, 0x00, 0x00, 9, 0, 0, 0]
+    );
+    assert!(od.data.text.is_read_locked());
+    assert!(od.data.text.try_get_mut().is_none());
+    let response = on_sdo_message!(sdo_server, od, [0x60, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x00, b'a', b'b', b'c', b'd', b'e', b'f', b'g']
+    );
+    let response = on_sdo_message!(sdo_server, od, [0x70, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(response.unwrap().data(), [0x1b, b'h', b'i', 0, 0, 0, 0, 0]);
+
+    // a size change after the size was indicated aborts the upload
+    on_sdo_message!(sdo_server, od, [0x40, 0x02, 0x00, 0x00, 0, 0, 0, 0]);
+    od.data.text.get_mut().push_str("jkl").unwrap();
+    let response = on_sdo_message!(sdo_server, od, [0x60, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x80, 0x02, 0x00, 0x00, 0x21, 0x00, 0x00, 0x08]
+    );
+    assert!(!od.data.text.is_read_locked());
+}
+
+#[test]
+fn test_interrupted_segmented_upload() {
+    #[derive(OdData)]
+    struct Data {
+        #[canopen(index = 1)]
+        obj: OdCell<[u8; 9]>,
+        #[canopen(index = 2)]
+        other: u8,
+    }
+
+    let mut od = Data {
+        obj: OdCell::new([1, 2, 3, 4, 5, 6, 7, 8, 9]),
+        other: 5,
+    }
+    .into_od();
+    let mut sdo_server = SdoServer::new(NODE_ID);
+
+    // a new request releases the lock of the unfinished upload
+    on_sdo_message!(sdo_server, od, [0x40, 0x01, 0x00, 0x00, 0, 0, 0, 0]);
+    on_sdo_message!(sdo_server, od, [0x60, 0, 0, 0, 0, 0, 0, 0]);
+    assert!(od.data.obj.is_read_locked());
+    let response = on_sdo_message!(sdo_server, od, [0x40, 0x02, 0x00, 0x00, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x4f, 0x02, 0x00, 0x00, 5, 0, 0, 0]
+    );
+    assert!(!od.data.obj.is_read_locked());
+
+    // so does a client that stops responding
+    on_sdo_message!(sdo_server, od, [0x40, 0x01, 0x00, 0x00, 0, 0, 0, 0]);
+    on_sdo_message!(sdo_server, od, [0x60, 0, 0, 0, 0, 0, 0, 0]);
+    assert!(sdo_server
+        .on_tick(Duration::from_millis(100), &mut od)
+        .is_none());
+    assert!(sdo_server
+        .on_tick(Duration::from_millis(1000), &mut od)
+        .is_none());
+    assert!(od.data.obj.is_read_locked());
+    let abort = sdo_server.on_tick(Duration::from_millis(1100), &mut od);
+    assert_eq!(
+        abort.unwrap().into_frame::<CanOpenFrame>().data(),
+        [0x80, 0x01, 0x00, 0x00, 0x00, 0x00, 0x04, 0x05]
+    );
+    assert!(!od.data.obj.is_read_locked());
+    assert!(sdo_server
+        .on_tick(Duration::from_millis(5000), &mut od)
+        .is_none());
+}
+
+#[test]
+fn test_limits() {
+    #[derive(OdData)]
+    struct Data {
+        #[canopen(index = 1, min = 100, max = 200)]
+        obj: u32,
+        #[canopen(index = 2, max = 1_000_000_000_000)]
+        large: u64,
+    }
+
+    let mut od = Data { obj: 100, large: 0 }.into_od();
+    let mut sdo_server = SdoServer::new(NODE_ID);
+
+    write(1, 0, &mut sdo_server, &mut od, &150u32.to_le_bytes()).unwrap();
+    assert_eq!(
+        write(1, 0, &mut sdo_server, &mut od, &201u32.to_le_bytes()),
+        Err(ProtocolError::Abort(SDOAbortCode::ValueTooHigh))
+    );
+    assert_eq!(
+        write(1, 0, &mut sdo_server, &mut od, &99u32.to_le_bytes()),
+        Err(ProtocolError::Abort(SDOAbortCode::ValueTooLow))
+    );
+    assert_eq!(od.data.obj, 150);
+
+    // segmented download
+    assert_eq!(
+        write(2, 0, &mut sdo_server, &mut od, &u64::MAX.to_le_bytes()),
+        Err(ProtocolError::Abort(SDOAbortCode::ValueTooHigh))
+    );
+    assert_eq!(od.data.large, 0);
+}
+
+#[test]
+fn test_limits_without_size() {
+    #[derive(OdData)]
+    struct Data {
+        #[canopen(index = 1, min = 100, max = 200)]
+        obj: u32,
+        #[canopen(index = 2, max = 1_000_000_000_000)]
+        large: u64,
+        #[canopen(index = 3, typ = u32, max = 10)]
+        cell: OdCell<[u8; 4]>,
+    }
+
+    let mut od = Data {
+        obj: 100,
+        large: 0,
+        cell: OdCell::new([0; 4]),
+    }
+    .into_od();
+    let mut sdo_server = SdoServer::new(NODE_ID);
+
+    // REQUEST_DOWNLOAD without SIZE_SPECIFIED, then a single segment of 4 bytes
+    on_sdo_message!(sdo_server, od, [0x20, 0x01, 0x00, 0x00, 0, 0, 0, 0]);
+    let response = on_sdo_message!(sdo_server, od, [0x07, 201, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x80, 0x01, 0x00, 0x00, 0x31, 0x00, 0x09, 0x06]
+    );
+    on_sdo_message!(sdo_server, od, [0x20, 0x01, 0x00, 0x00, 0, 0, 0, 0]);
+    let response = on_sdo_message!(sdo_server, od, [0x07, 150, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(od.data.obj, 150);
+
+    // the limits are checked on the assembled value
+    on_sdo_message!(sdo_server, od, [0x20, 0x02, 0x00, 0x00, 0, 0, 0, 0]);
+    on_sdo_message!(
+        sdo_server,
+        od,
+        [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+    );
+    let response = on_sdo_message!(sdo_server, od, [0x1d, 0xff, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x80, 0x02, 0x00, 0x00, 0x31, 0x00, 0x09, 0x06]
+    );
+    assert_eq!(od.data.large, 0);
+
+    // other objects are written in parts, which can't be checked
+    let response = on_sdo_message!(sdo_server, od, [0x20, 0x03, 0x00, 0x00, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x80, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01, 0x06]
+    );
+    assert_eq!(*od.data.cell.get(), [0; 4]);
+}
+
+#[test]
+fn test_validate() {
+    #[derive(OdData)]
+    struct Motor {
+        #[canopen(index = 1)]
+        running: bool,
+        #[canopen(index = 2, validate = check_speed)]
+        speed: u16,
+        #[canopen(index = 3, validate = check_position)]
+        position: u64,
+    }
+
+    fn check_speed(motor: &Motor, speed: u16) -> Result<(), ODError> {
+        if motor.running {
+            Err(ODError::DeviceStateError)
+        } else if !speed.is_multiple_of(10) {
+            Err(ODError::InvalidValue)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_position(_: &Motor, position: u64) -> Result<(), ODError> {
+        if position > u32::MAX as u64 {
+            Err(ODError::ValueTooHigh)
+        } else {
+            Ok(())
+        }
+    }
+
+    let mut od = Motor {
+        running: false,
+        speed: 0,
+        position: 0,
+    }
+    .into_od();
+    let mut sdo_server = SdoServer::new(NODE_ID);
+
+    write(2, 0, &mut sdo_server, &mut od, &100u16.to_le_bytes()).unwrap();
+    assert_eq!(
+        write(2, 0, &mut sdo_server, &mut od, &105u16.to_le_bytes()),
+        Err(ProtocolError::Abort(SDOAbortCode::InvalidValue))
+    );
+    od.data.running = true;
+    assert_eq!(
+        write(2, 0, &mut sdo_server, &mut od, &200u16.to_le_bytes()),
+        Err(ProtocolError::Abort(SDOAbortCode::DeviceStateError))
+    );
+    assert_eq!(od.data.speed, 100);
+    // the size is checked before the value
+    assert_eq!(
+        write(2, 0, &mut sdo_server, &mut od, &[0]),
+        Err(ProtocolError::Abort(SDOAbortCode::TooShort))
+    );
+
+    // segmented download
+    assert_eq!(
+        write(3, 0, &mut sdo_server, &mut od, &u64::MAX.to_le_bytes()),
+        Err(ProtocolError::Abort(SDOAbortCode::ValueTooHigh))
+    );
+    write(3, 0, &mut sdo_server, &mut od, &5u64.to_le_bytes()).unwrap();
+    assert_eq!(od.data.position, 5);
+}
+
+#[test]
+fn test_validate_without_size() {
+    #[derive(OdData)]
+    struct Motor {
+        #[canopen(index = 1, validate = check_position)]
+        position: u64,
+        #[canopen(index = 2, validate = check_name)]
+        name: OdCell<[u8; 4]>,
+    }
+
+    fn check_position(_: &Motor, position: u64) -> Result<(), ODError> {
+        if position > u32::MAX as u64 {
+            Err(ODError::ValueTooHigh)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_name(_: &Motor, _: u32) -> Result<(), ODError> {
+        Ok(())
+    }
+
+    let mut od = Motor {
+        position: 0,
+        name: OdCell::new([0; 4]),
+    }
+    .into_od();
+    let mut sdo_server = SdoServer::new(NODE_ID);
+
+    // REQUEST_DOWNLOAD without SIZE_SPECIFIED, then the 8 bytes in two segments
+    on_sdo_message!(sdo_server, od, [0x20, 0x01, 0x00, 0x00, 0, 0, 0, 0]);
+    on_sdo_message!(
+        sdo_server,
+        od,
+        [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+    );
+    let response = on_sdo_message!(sdo_server, od, [0x1d, 0xff, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x80, 0x01, 0x00, 0x00, 0x31, 0x00, 0x09, 0x06]
+    );
+    assert_eq!(od.data.position, 0);
+
+    on_sdo_message!(sdo_server, od, [0x20, 0x01, 0x00, 0x00, 0, 0, 0, 0]);
+    on_sdo_message!(sdo_server, od, [0x00, 5, 0, 0, 0, 0, 0, 0]);
+    let response = on_sdo_message!(sdo_server, od, [0x1d, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(od.data.position, 5);
+
+    // the validate function can't run on a value written in parts
+    let response = on_sdo_message!(sdo_server, od, [0x20, 0x02, 0x00, 0x00, 0, 0, 0, 0]);
+    assert_eq!(
+        response.unwrap().data(),
+        [0x80, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01, 0x06]
+    );
+}
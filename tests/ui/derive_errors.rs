@@ -1,4 +1,4 @@
-use canopen::objectdictionary::OdData;
+use canopen::objectdictionary::{OdArray, OdData};
 
 #[derive(OdData)]
 struct Test {
@@ -25,4 +25,71 @@ struct Test2 {
     c2: u8,
 }
 
+#[derive(OdData)]
+struct Test3 {
+    #[canopen(index = 1, min = 0)]
+    a: bool,
+    #[canopen(index = 2, min = 10, max = 5)]
+    b: u8,
+    #[canopen(index = 3, max = 300)]
+    c: u8,
+}
+
+#[derive(OdData)]
+#[canopen(record)]
+struct Test4 {
+    #[canopen(index = 1)]
+    a: u8,
+    #[canopen(subindex = 0)]
+    b: u8,
+}
+
+#[derive(OdData)]
+struct Test9 {
+    #[canopen(index = 0x0000)]
+    a: u8,
+    #[canopen(index = 0x0100)]
+    b: u8,
+    #[canopen(index = 0x1017)]
+    c: u32,
+    #[canopen(record = "Record", index = 0x2000)]
+    #[canopen(index = 0x2000, subindex = 1)]
+    d: u8,
+}
+
+#[derive(OdData)]
+struct Test10 {
+    #[canopen(index = 0x2000, typ = u16)]
+    a: u8,
+    #[canopen(array = "Array", index = 0x2001, size = 4, typ = UNSIGNED8)]
+    b: OdArray<u8, 3>,
+    #[canopen(array = "Array", index = 0x2002, size = 2, typ = UNSIGNED16)]
+    c: OdArray<i16, 2>,
+}
+
+#[derive(OdData)]
+#[canopen(record)]
+struct Test11Record {
+    a: u8,
+}
+
+#[derive(OdData)]
+struct Test11<const M: usize> {
+    #[canopen(record = "Record", index = 0x2000)]
+    a: Test11Record,
+}
+
+#[derive(OdData)]
+#[canopen(eds_path = "test12.eds")]
+struct Test12 {
+    #[canopen(record = "Record", index = 0x2000)]
+    a: Test12Record,
+}
+
+#[derive(OdData)]
+#[canopen(record)]
+struct Test12Record {
+    a: u8,
+}
+
 fn main() {}
@@ -0,0 +1,21 @@
+use canopen::objectdictionary::datalink::OdValue;
+
+#[derive(OdValue)]
+enum Test {
+    A,
+}
+
+#[derive(OdValue)]
+#[repr(u8)]
+enum Test2 {
+    A(u8),
+}
+
+#[derive(OdValue)]
+#[canopen(reserved = 0x100)]
+struct Test3(u8);
+
+#[derive(OdValue)]
+struct Test4(f32);
+
+fn main() {}